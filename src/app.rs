@@ -1,70 +1,227 @@
-use std::future::Future;
-use std::sync::mpsc::{channel, Receiver, Sender};
 use egui::{
-    Color32, ColorImage, Frame, Pos2, Rect, Rounding, Sense, TextureHandle, TextureId,
-    TextureOptions, Vec2,
+    Color32, Frame, Key, KeyboardShortcut, Modifiers, Pos2, Rect, Sense, TextureOptions, Vec2,
 };
 use image::DynamicImage;
-use krilla::PageSettings;
-//use pdf_writer::Pdf;
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum Units {
-    Inches,
-    Centimeters,
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+use crate::banner::{LetterStyle, TextBanner};
+use crate::caption::{Caption, CaptionFont, TextSize};
+use crate::error::{Error, ParseLengthError};
+use crate::export::{PdfExport, TileJob};
+use crate::history::History;
+use crate::layout::{
+    effective_dpi, max_overlap, parse_length, parse_signed_length, Anchor, Fit, LengthInput, Page,
+    Roll, Sheet, Size, SizeConstraint, SolvedSize, TileLayout, Units, MAX_PAGE_LENGTH,
+};
+use crate::overlay::Overlay;
+use crate::project::{self, LayoutSettings, Project};
+use crate::resample::{Resample, ResampleFilter};
+use crate::source::SourceImage;
+use crate::tiled_texture::{TextureChunk, TiledTexture};
+
+/// Progress reports sent from a running export back to the UI.
+enum ExportMessage {
+    Progress { done: u32, total: u32 },
+    Finished,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum Page {
-    Letter,
-    A4,
-    Legal,
-    Tabloid,
+/// Screen resolution assumed at a scale factor of 1, when the user has not entered their own.
+const DEFAULT_SCREEN_DPI: f32 = 96.0;
+
+/// Print resolution below which the side panel warns, until the user picks another.
+const DEFAULT_MIN_DPI: f32 = 150.0;
+
+/// An image decoded in the background, ready to be shown.
+struct LoadedImage {
+    source: SourceImage,
+    /// The preview texture's pixels.
+    chunks: Vec<TextureChunk>,
+    /// The layout saved alongside the image, when it came from a project file.
+    settings: Option<LayoutSettings>,
+    /// What the poster is called, usually the image's file name without extension.
+    name: String,
 }
 
-impl Page {
-    fn size(&self) -> Vec2 {
-        match self {
-            Page::A4 => Vec2::new(8.3, 11.7),
-            Page::Legal => Vec2::new(8.5, 14.0),
-            Page::Letter => Vec2::new(8.5, 11.0),
-            Page::Tabloid => Vec2::new(11.0, 17.0),
-        }
-    }
+/// A file's name and contents, as sent to `image_channel`, `project_channel` and `font_channel`.
+type FileData = (String, Vec<u8>);
+
+/// A font file for the caption at the given index, as sent to
+/// `caption_font_channel`, with the captions as they were when it was chosen.
+type CaptionFontFile = (usize, Vec<Caption>, FileData);
+
+type LoadResult = Result<LoadedImage, Error>;
+
+/// A load's result with the number `load_in_background` gave it.
+type NumberedLoad = (u64, LoadResult);
+
+/// Decodes an image file, or the image and layout in a project file, and
+/// prepares its preview. This is the slow part of loading.
+fn load_image(file: FileData, is_project: bool, max_texture_side: usize) -> LoadResult {
+    let (file_name, data) = file;
+    let mut name = file_name_stem(&file_name);
+    let (source, settings) = if is_project {
+        let project = Project::from_bytes(&data)?;
+        name = project.name.unwrap_or(name);
+        let mut source = SourceImage::decode(project.image.clone())?;
+        // Keep the embedded bytes as they are, so the PDF comes out identical.
+        source.bytes = project.image;
+        source.dpi = project.image_dpi;
+        (source, Some(project.settings))
+    } else {
+        (SourceImage::decode(data)?, None)
+    };
+    let chunks = TiledTexture::prepare(&source.image, max_texture_side);
+    Ok(LoadedImage {
+        source,
+        chunks,
+        settings,
+        name,
+    })
+}
+
+/// The egui font family a caption's font file is registered under. Files from
+/// different families are often named alike, e.g. `Regular.ttf`, so the size
+/// tells them apart.
+fn font_family_name(name: &str, data: &[u8]) -> String {
+    format!("{name} ({} bytes)", data.len())
+}
+
+/// `name` without its extension, or `name` itself if it has none.
+fn file_name_stem(name: &str) -> String {
+    std::path::Path::new(name)
+        .file_stem()
+        .map_or(name.to_owned(), |stem| stem.to_string_lossy().into_owned())
 }
+
+/// Tooltip for fields accepting [`parse_length`] expressions.
+const LENGTH_HINT: &str = "Drag, or type a length such as 1.2 m, 47 1/4 in or 150%";
+
+/// Zoom and scroll position of the page-grid preview.
+#[derive(Default, Clone, Copy, PartialEq)]
+struct PreviewView {
+    /// Screen points per inch of paper, or `None` to fit every page into view.
+    scale: Option<f32>,
+    /// Offset of the grid's top-left corner from the canvas's.
+    pan: Vec2,
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
-//#[derive(serde::Deserialize, serde::Serialize)]
-//#[serde(default)] // if we add new fields, give them default values when deserializing old state
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct EtracerApp {
-    // Example stuff:
-    label: String,
-    image_channel: (Sender<Vec<u8>>, Receiver<Vec<u8>>),
+    // Channels, the loaded image and textures only live for one session.
+    #[serde(skip)]
+    image_channel: (Sender<FileData>, Receiver<FileData>),
+    #[serde(skip)]
+    project_channel: (Sender<FileData>, Receiver<FileData>),
+    #[serde(skip)]
+    font_channel: (Sender<FileData>, Receiver<FileData>),
+    /// Font files for captions, with the index of the caption they are for.
+    #[serde(skip)]
+    caption_font_channel: (Sender<CaptionFontFile>, Receiver<CaptionFontFile>),
+    #[serde(skip)]
+    loaded_channel: (Sender<NumberedLoad>, Receiver<NumberedLoad>),
+    /// Number of images still being decoded.
+    #[serde(skip)]
+    loading: usize,
+    /// Whether the Ctrl+V or Cmd+V being held was already reported as a paste
+    /// of text, see [`Self::handle_paste`].
+    #[serde(skip)]
+    pasted_text: bool,
+    /// Number of the last load started. Earlier loads finishing later are
+    /// dropped, so the file opened last is the one shown.
+    #[serde(skip)]
+    latest_load: u64,
+    #[serde(skip)]
+    error_channel: (Sender<Error>, Receiver<Error>),
+    #[serde(skip)]
+    export_channel: (Sender<ExportMessage>, Receiver<ExportMessage>),
+    /// Pages written so far and in total while an export is running.
+    #[serde(skip)]
+    export_progress: Option<(u32, u32)>,
+    /// Set to ask the running export to stop after the current page.
+    #[serde(skip)]
+    export_cancel: Arc<AtomicBool>,
+    /// Error messages shown until the user dismisses them.
+    #[serde(skip)]
+    notifications: Vec<String>,
+    #[serde(skip)]
     image_data: Option<DynamicImage>,
+    #[serde(skip)]
     raw_data: Option<Vec<u8>>,
-    texture_handle: Option<TextureHandle>,
-    texture_id: Option<TextureId>,
-    //#[serde(skip)] // This how you opt-out of serialization of a field
-    desired_width: f32,
-    desired_height: f32,
-    units: Units,
-    page_size: Page,
-    maintain_aspect_ratio: bool,
+    /// Resolution embedded in the loaded image, in dots per inch.
+    #[serde(skip)]
+    image_dpi: Option<(f32, f32)>,
+    /// The loaded image is an SVG, exported as vectors.
+    #[serde(skip)]
+    image_vector: bool,
+    /// The loaded image's file name without extension, used as the PDF title.
+    #[serde(skip)]
+    image_name: Option<String>,
+    /// The preview of `image_data`, in as many pieces as the GPU needs.
+    #[serde(skip)]
+    texture: Option<TiledTexture>,
+    settings: LayoutSettings,
+    #[serde(skip)]
+    preview: PreviewView,
+    /// Monitor resolution entered by the user; `None` guesses it from the scale factor.
+    screen_dpi: Option<f32>,
+    /// Written into the Author field of exported PDFs.
+    author: String,
+    /// Print resolution below which a warning is shown, in dots per inch.
+    min_dpi: f32,
+    /// Draw the preview with nearest-neighbour sampling so pixels stay visible.
+    show_pixels: bool,
+    /// The text banner last set up, kept for the next one.
+    banner: TextBanner,
+    /// Name and contents of the font picked for banners; `None` uses the built-in one.
+    #[serde(skip)]
+    banner_font: Option<FileData>,
+    /// Families of the caption font files added to egui's fonts for the
+    /// preview, named by [`font_family_name`].
+    #[serde(skip)]
+    caption_fonts: Vec<String>,
+    /// Earlier states of `settings`, for undo and redo.
+    #[serde(skip)]
+    history: History,
 }
 
 impl Default for EtracerApp {
     fn default() -> Self {
         Self {
-            // Example stuff:
-            label: "Hello World!".to_owned(),
             image_channel: channel(),
+            project_channel: channel(),
+            font_channel: channel(),
+            caption_font_channel: channel(),
+            loaded_channel: channel(),
+            loading: 0,
+            pasted_text: false,
+            latest_load: 0,
+            error_channel: channel(),
+            export_channel: channel(),
+            export_progress: None,
+            export_cancel: Arc::new(AtomicBool::new(false)),
+            notifications: Vec::new(),
             image_data: None,
             raw_data: None,
-            desired_width: 8.26,
-            desired_height: 15.0,
-            maintain_aspect_ratio: false,
-            texture_handle: None,
-            texture_id: None,
-            units: Units::Inches,
-            page_size: Page::Letter,
+            image_dpi: None,
+            image_vector: false,
+            image_name: None,
+            texture: None,
+            settings: LayoutSettings::default(),
+            preview: PreviewView::default(),
+            screen_dpi: None,
+            author: String::new(),
+            min_dpi: DEFAULT_MIN_DPI,
+            show_pixels: false,
+            banner: TextBanner::default(),
+            banner_font: None,
+            caption_fonts: Vec::new(),
+            history: History::new(LayoutSettings::default()),
         }
     }
 }
@@ -78,25 +235,84 @@ impl EtracerApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            //return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
-
-        Default::default()
+        let mut app: Self = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+            .unwrap_or_default();
+        app.history = History::new(app.settings.clone());
+        #[cfg(target_arch = "wasm32")]
+        app.listen_for_pasted_files(&cc.egui_ctx);
+        app
     }
 }
 
 impl eframe::App for EtracerApp {
     /// Called by the frame work to save state before shutdown.
-    // fn save(&mut self, storage: &mut dyn eframe::Storage) {
-    //     eframe::set_value(storage, eframe::APP_KEY, self);
-    // }
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, self);
+    }
 
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Put your widgets into a `SidePanel`, `TopBottomPanel`, `CentralPanel`, `Window` or `Area`.
         // For inspiration and more examples, go to https://emilk.github.io/egui
         ctx.set_theme(egui::Theme::Dark);
+        while let Ok(err) = self.error_channel.1.try_recv() {
+            // An edit field may report the same text twice as it loses focus.
+            let message = err.to_string();
+            if !self.notifications.contains(&message) {
+                self.notifications.push(message);
+            }
+        }
+        while let Ok(data) = self.image_channel.1.try_recv() {
+            self.load_in_background(ctx, data, false);
+        }
+        while let Ok(data) = self.project_channel.1.try_recv() {
+            self.load_in_background(ctx, data, true);
+        }
+        while let Ok(font) = self.font_channel.1.try_recv() {
+            self.banner_font = Some(font);
+        }
+        while let Ok((index, captions, (name, data))) = self.caption_font_channel.1.try_recv() {
+            // The index may point at another caption by now.
+            if captions != self.settings.captions {
+                self.notifications.push(
+                    "The captions changed while the font was chosen; choose it again.".into(),
+                );
+                continue;
+            }
+            let font = CaptionFont::File {
+                name,
+                data: Arc::new(data),
+            };
+            match font.load() {
+                Ok(_) => self.settings.captions[index].font = font,
+                Err(err) => self.notifications.push(err.to_string()),
+            }
+        }
+        while let Ok((number, loaded)) = self.loaded_channel.1.try_recv() {
+            self.loading -= 1;
+            if number != self.latest_load {
+                continue;
+            }
+            match loaded {
+                Ok(loaded) => self.set_image(ctx, loaded),
+                Err(err) => self.notifications.push(err.to_string()),
+            }
+        }
+        while let Ok(message) = self.export_channel.1.try_recv() {
+            self.export_progress = match message {
+                ExportMessage::Progress { done, total } => Some((done, total)),
+                ExportMessage::Finished => None,
+            };
+        }
+        self.register_caption_fonts(ctx);
+        self.apply_size_constraint();
+        self.clamp_overlap();
+        self.handle_undo_shortcuts(ctx);
+        self.handle_dropped_files(ctx);
+        #[cfg(not(target_arch = "wasm32"))]
+        self.handle_paste(ctx);
         let is_web = cfg!(target_arch = "wasm32");
         if !is_web {
             egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
@@ -138,12 +354,44 @@ impl eframe::App for EtracerApp {
                     let file_opt = open_file.await;
                     if let Some(file) = file_opt {
                         let data = file.read().await;
-                        sender.send(data);
+                        let _ = sender.send((file.file_name(), data));
                         ctx.request_repaint();
                     }
                 });
             }
 
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui
+                .button("paste")
+                .on_hover_text("Load the image on the clipboard")
+                .clicked()
+            {
+                self.paste_image(ui.ctx(), None);
+            }
+
+            ui.horizontal(|ui| {
+                if ui.button("open project").clicked() {
+                    let sender = self.project_channel.0.clone();
+                    let open_file = rfd::AsyncFileDialog::new()
+                        .add_filter("etracer project", &[project::EXTENSION])
+                        .pick_file();
+                    let ctx = ui.ctx().clone();
+                    execute(async move {
+                        if let Some(file) = open_file.await {
+                            let _ = sender.send((file.file_name(), file.read().await));
+                            ctx.request_repaint();
+                        }
+                    });
+                }
+                let btn_save_project = ui
+                    .add_enabled(self.raw_data.is_some(), egui::Button::new("save project"))
+                    .on_disabled_hover_text("Load an image first.");
+                if btn_save_project.clicked() {
+                    self.save_project(ui.ctx());
+                }
+            });
+            self.show_banner(ui);
+
             match &self.image_data {
                 Some(data) => ui.label(format!(
                     "Loaded image with dimensions: {} x {}.",
@@ -152,261 +400,1545 @@ impl eframe::App for EtracerApp {
                 )),
                 None => ui.label("No Image Loaded."),
             };
+            // The size every pixel prints at the embedded resolution, in inches.
+            let native_size =
+                self.image_data
+                    .as_ref()
+                    .zip(self.image_dpi)
+                    .map(|(data, (dpi_x, dpi_y))| {
+                        (data.width() as f32 / dpi_x, data.height() as f32 / dpi_y)
+                    });
+            if let (Some((dpi_x, dpi_y)), Some((native_width, native_height))) =
+                (self.image_dpi, native_size)
+            {
+                ui.label(format!("Embedded resolution: {dpi_x:.0} x {dpi_y:.0} dpi."));
+                let manual = self.settings.size_constraint.is_none();
+                ui.add_enabled_ui(manual, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Print at native size")
+                            .on_hover_text(
+                                "Size the poster so every pixel prints at the embedded resolution",
+                            )
+                            .clicked()
+                        {
+                            self.settings.desired_width = native_width;
+                            self.settings.desired_height = native_height;
+                        }
+                        let mut percent = self.settings.desired_width / native_width * 100.0;
+                        let scale = ui
+                            .add(
+                                egui::DragValue::new(&mut percent)
+                                    .range(1.0..=10000.0)
+                                    .suffix("%"),
+                            )
+                            .on_hover_text("Scale by a percentage of the native size");
+                        if scale.changed() {
+                            self.settings.desired_width = native_width * percent / 100.0;
+                            self.settings.desired_height = native_height * percent / 100.0;
+                        }
+                    })
+                });
+            }
 
             ui.separator();
+            self.show_size_constraint(ui);
+            let manual = self.settings.size_constraint.is_none();
             ui.add(egui::Checkbox::new(
-                &mut self.maintain_aspect_ratio,
+                &mut self.settings.maintain_aspect_ratio,
                 "Maintain aspect ratio",
             ));
-            ui.add(
-                egui::Slider::from_get_set(0.1..=100.0, |v| match v {
-                    Some(val) => {
-                        self.desired_width = match self.units {
-                            Units::Inches => val as f32,
-                            Units::Centimeters => val as f32 / 2.54,
-                        };
-                        if self.maintain_aspect_ratio && self.image_data.is_some() {
-                            self.desired_height = (self.desired_width as f64
-                                * self.image_data.as_ref().unwrap().height() as f64
-                                / self.image_data.as_ref().unwrap().width() as f64)
-                                as f32;
-                        }
-                        val
+            if !self.settings.maintain_aspect_ratio {
+                self.show_fit(ui);
+            }
+            let units = self.settings.units;
+            let aspect = self
+                .image_data
+                .as_ref()
+                .filter(|_| self.settings.maintain_aspect_ratio)
+                .map(|data| data.height() as f32 / data.width() as f32);
+            egui::Grid::new("size").num_columns(2).show(ui, |ui| {
+                let mut width = self.settings.desired_width;
+                let native_width = native_size.map(|(width, _)| width);
+                ui.label("Desired width");
+                if ui
+                    .add_enabled(
+                        manual,
+                        length_drag_value(
+                            &mut width,
+                            units,
+                            native_width,
+                            self.error_channel.0.clone(),
+                        ),
+                    )
+                    .on_hover_text(LENGTH_HINT)
+                    .changed()
+                {
+                    self.settings.desired_width = width;
+                    if let Some(aspect) = aspect {
+                        self.settings.desired_height = width * aspect;
                     }
-                    None => match self.units {
-                        Units::Inches => self.desired_width as f64,
-                        Units::Centimeters => self.desired_width as f64 * 2.54,
-                    },
-                })
-                .text("Desired width"),
-            );
-            ui.add(
-                egui::Slider::from_get_set(0.1..=100.0, |v| match v {
-                    Some(val) => {
-                        self.desired_height = match self.units {
-                            Units::Inches => val as f32,
-                            Units::Centimeters => val as f32 / 2.54,
-                        };
-                        if self.maintain_aspect_ratio && self.image_data.is_some() {
-                            self.desired_width = (self.desired_height as f64
-                                * self.image_data.as_ref().unwrap().width() as f64
-                                / self.image_data.as_ref().unwrap().height() as f64)
-                                as f32;
-                        }
-                        val
+                }
+                ui.end_row();
+
+                let mut height = self.settings.desired_height;
+                let native_height = native_size.map(|(_, height)| height);
+                ui.label("Desired height");
+                if ui
+                    .add_enabled(
+                        manual,
+                        length_drag_value(
+                            &mut height,
+                            units,
+                            native_height,
+                            self.error_channel.0.clone(),
+                        ),
+                    )
+                    .on_hover_text(LENGTH_HINT)
+                    .changed()
+                {
+                    self.settings.desired_height = height;
+                    if let Some(aspect) = aspect {
+                        self.settings.desired_width = height / aspect;
                     }
-                    None => match self.units {
-                        Units::Inches => self.desired_height as f64,
-                        Units::Centimeters => self.desired_height as f64 * 2.54,
-                    },
-                })
-                .text("Desired height"),
-            );
+                }
+                ui.end_row();
+
+                let mut overlap = self.settings.overlap;
+                ui.label("Overlap");
+                if ui
+                    .add(
+                        offset_drag_value(&mut overlap, units, self.error_channel.0.clone())
+                            .range(0.0..=f64::MAX),
+                    )
+                    .on_hover_text("How much neighbouring pages share, to leave room for glue.")
+                    .changed()
+                {
+                    self.settings.overlap = overlap;
+                    self.clamp_overlap();
+                }
+                ui.end_row();
+            });
+
+            self.show_print_resolution(ui);
 
             egui::ComboBox::from_label("Units")
-                .selected_text(format!("{:?}", self.units))
+                .selected_text(format!("{:?}", self.settings.units))
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(
-                        &mut self.units,
-                        Units::Inches,
-                        format!("{:?}", Units::Inches),
-                    );
-                    ui.selectable_value(
-                        &mut self.units,
-                        Units::Centimeters,
-                        format!("{:?}", Units::Centimeters),
-                    );
+                    for units in Units::ALL {
+                        ui.selectable_value(&mut self.settings.units, units, format!("{units:?}"));
+                    }
                 });
-            let multiplier = match self.units {
-                Units::Inches => 1.0,
-                Units::Centimeters => 2.54,
+            let selected_page = match self.settings.roll {
+                Some(_) => "Roll paper".to_owned(),
+                None => format!("{:?}", self.settings.page_size),
             };
             egui::ComboBox::from_label("Page")
-                .selected_text(format!("{:?}", self.page_size))
+                .selected_text(selected_page)
                 .show_ui(ui, |ui| {
-                    ui.selectable_value(
-                        &mut self.page_size,
-                        Page::Letter,
-                        format!(
-                            "{:?} ({:.2}x{:.2})",
-                            Page::Letter,
-                            Page::Letter.size().x * multiplier,
-                            Page::Letter.size().y * multiplier
-                        ),
-                    );
-                    ui.selectable_value(
-                        &mut self.page_size,
-                        Page::A4,
-                        format!(
-                            "{:?} ({:.2}x{:.2})",
-                            Page::A4,
-                            Page::A4.size().x * multiplier,
-                            Page::A4.size().y * multiplier
-                        ),
-                    );
-                    ui.selectable_value(
-                        &mut self.page_size,
-                        Page::Legal,
-                        format!(
-                            "{:?} ({:.2}x{:.2})",
-                            Page::Legal,
-                            Page::Legal.size().x * multiplier,
-                            Page::Legal.size().y * multiplier
-                        ),
-                    );
-                    ui.selectable_value(
-                        &mut self.page_size,
-                        Page::Tabloid,
-                        format!(
-                            "{:?} ({:.2}x{:.2})",
-                            Page::Tabloid,
-                            Page::Tabloid.size().x * multiplier,
-                            Page::Tabloid.size().y * multiplier
-                        ),
-                    );
+                    for page in Page::ALL {
+                        let selected =
+                            self.settings.roll.is_none() && self.settings.page_size == page;
+                        let text = format!(
+                            "{:?} ({:.2}x{:.2} {})",
+                            page,
+                            self.settings.units.from_inches(page.size().width),
+                            self.settings.units.from_inches(page.size().height),
+                            self.settings.units.suffix()
+                        );
+                        if ui.selectable_label(selected, text).clicked() {
+                            self.settings.page_size = page;
+                            self.settings.roll = None;
+                        }
+                    }
+                    let is_roll = self.settings.roll.is_some();
+                    if ui.selectable_label(is_roll, "Roll paper").clicked() && !is_roll {
+                        self.settings.roll = Some(Roll::default());
+                    }
                 });
+            if self.settings.roll.is_none() {
+                ui.add_enabled(
+                    self.settings.size_constraint.is_none(),
+                    egui::Checkbox::new(&mut self.settings.landscape, "Landscape"),
+                )
+                .on_disabled_hover_text("Chosen by the size requirement.");
+            }
+            self.show_roll(ui);
+            self.show_sheet(ui);
             ui.separator();
 
-            if ui.button("save").clicked() {
-                let z = rfd::AsyncFileDialog::new().set_title("Save resized.pdf").set_file_name("resized.pdf").save_file();
-                let d = self.raw_data.as_ref().unwrap().clone();
-                let dh = self.desired_height;
-                let dw = self.desired_width;
-                let p = self.page_size;
-                execute(async move {
+            ui.checkbox(&mut self.settings.crop_tiles, "Crop image per page")
+                .on_hover_text(
+                    "Embed only the part of the image each page shows. \
+                     Pages print faster and can be extracted on their own.",
+                );
+            ui.horizontal(|ui| {
+                ui.label("Author");
+                ui.text_edit_singleline(&mut self.author)
+                    .on_hover_text("Stored in the PDF's document properties");
+            });
+            ui.checkbox(&mut self.settings.print_reminder, "Print reminder on pages")
+                .on_hover_text("Remind on every page to print at 100%, without fit to page.");
+            self.show_captions(ui);
+            ui.collapsing("Overlays", |ui| {
+                ui.label("Each is a layer that can be hidden in the PDF viewer.");
+                for overlay in Overlay::ALL {
+                    let mut enabled = self.settings.overlays.is_enabled(overlay);
+                    if ui.checkbox(&mut enabled, overlay.name()).changed() {
+                        self.settings.overlays.set(overlay, enabled);
+                    }
+                }
+            });
+            let btn_save = ui
+                .add_enabled(
+                    self.raw_data.is_some() && self.export_progress.is_none(),
+                    egui::Button::new("save"),
+                )
+                .on_disabled_hover_text("Load an image first.");
+            if let (true, Some(d)) = (btn_save.clicked(), &self.raw_data) {
+                let z = rfd::AsyncFileDialog::new()
+                    .set_title("Save PDF")
+                    .set_file_name(format!("{}.pdf", self.poster_name()))
+                    .save_file();
+                let d = d.clone();
+                let job = self.job();
+                let name = self.poster_name().to_owned();
+                let error_sender = self.error_channel.0.clone();
+                let progress_sender = self.export_channel.0.clone();
+                let cancel = Arc::new(AtomicBool::new(false));
+                self.export_cancel = cancel.clone();
+                let ctx = ui.ctx().clone();
+                execute_local(move || async move {
                     let q = z.await;
                     if let Some(file) = q {
-                        let res = file.write(&generate_pdf(dw, dh, p, &d)).await;
+                        let result =
+                            save_pdfs(file, &job, &d, &name, &progress_sender, &cancel, &ctx).await;
+                        if let Err(err) = result {
+                            let _ = error_sender.send(err);
+                        }
                     }
+                    let _ = progress_sender.send(ExportMessage::Finished);
+                    ctx.request_repaint();
                 });
             }
+            if let Some((done, total)) = self.export_progress {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                            .desired_width(160.0)
+                            .text(format!("Page {done} of {total}")),
+                    );
+                    if ui.button("Cancel").clicked() {
+                        self.export_cancel.store(true, Ordering::Relaxed);
+                    }
+                });
+            }
+            ui.separator();
+            self.show_history(ui);
             ui.with_layout(egui::Layout::bottom_up(egui::Align::LEFT), |ui| {
                 egui::warn_if_debug_build(ui);
             });
         });
+        // A slider drag becomes a single edit once the mouse is released.
+        if !ctx.input(|i| i.pointer.any_down()) && !ctx.wants_keyboard_input() {
+            self.history.record(&self.settings);
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
-            if let Ok(img_data) = self.image_channel.1.try_recv() {
-                let mut image = egui::Image::from_bytes("bytes://image.png", img_data.clone());
-                image = image.max_size(Vec2::new(400.0, 400.0));
-                image = image.fit_to_original_size(1.0);
-
-                self.raw_data = Some(img_data.clone());
-                let res = ctx.try_load_texture(
-                    image.uri().unwrap(),
-                    TextureOptions::default(),
-                    egui::SizeHint::Scale(egui::emath::OrderedFloat(1.0)),
-                );
-                let im = image::load_from_memory(&img_data).unwrap();
-                self.image_data = Some(im.clone());
-                let size = [im.width() as _, im.height() as _];
-                let ci = ColorImage::from_rgba_unmultiplied(
-                    size,
-                    im.to_rgba8().as_flat_samples().as_slice(),
-                );
-                self.texture_handle =
-                    Some(ctx.load_texture("my_image", ci, TextureOptions::default()));
-                self.texture_id = Some(TextureId::from(self.texture_handle.as_ref().unwrap()));
-            }
-
-            Frame::canvas(ui.style()).show(ui, |ui| {
-                let draw_area = ui.available_rect_before_wrap();
-                let (response, painter) =
-                    ui.allocate_painter(ui.available_size_before_wrap(), Sense::hover());
-
-                let page_count_horizontal =
-                    calculate_page_count(self.desired_width, self.page_size.size().x);
-                let page_count_vertical =
-                    calculate_page_count(self.desired_height, self.page_size.size().y);
-                let margin_frac = 0.05;
-                let mut display_page_height = draw_area.height()
-                    / (page_count_vertical as f32 * (1.0 + margin_frac) - margin_frac);
-                let mut display_page_width = draw_area.width()
-                    / (page_count_horizontal as f32 * (1.0 + margin_frac) - margin_frac);
-                if display_page_width
-                    >= display_page_height * self.page_size.size().x / self.page_size.size().y
-                {
-                    display_page_width =
-                        display_page_height * self.page_size.size().x / self.page_size.size().y;
-                } else {
-                    display_page_height =
-                        display_page_width * self.page_size.size().y / self.page_size.size().x;
-                }
+            self.show_preview_controls(ui);
+            Frame::canvas(ui.style()).show(ui, |ui| self.show_preview(ui));
+        });
 
-                let offset_vertical = ((page_count_vertical as f32 * self.page_size.size().y
-                    - self.desired_height)
-                    / 2.0)
-                    / self.page_size.size().y
-                    * display_page_height;
-                let offset_horizontal = ((page_count_horizontal as f32 * self.page_size.size().x
-                    - self.desired_width)
-                    / 2.0)
-                    / self.page_size.size().x
-                    * display_page_width;
-
-                for y in 0..page_count_vertical {
-                    for x in 0..page_count_horizontal {
-                        let display_page_start = Pos2::new(
-                            x as f32 * (display_page_width + display_page_width * margin_frac),
-                            y as f32 * (display_page_height + display_page_height * margin_frac),
-                        );
-                        let display_page_end = Pos2::new(
-                            x as f32 * (display_page_width + display_page_width * margin_frac)
-                                + display_page_width,
-                            y as f32 * (display_page_height + display_page_height * margin_frac)
-                                + display_page_height,
-                        );
-                        painter.rect_filled(
-                            Rect::from_min_max(display_page_start, display_page_end)
-                                .translate(draw_area.min.to_vec2()),
-                            2.0,
-                            Color32::WHITE,
+        show_drop_hint(ctx);
+        self.show_notifications(ctx);
+    }
+}
+
+impl EtracerApp {
+    /// Decodes `data`, an image or a project file, without blocking the UI.
+    /// The result arrives on `loaded_channel`.
+    fn load_in_background(&mut self, ctx: &egui::Context, file: FileData, is_project: bool) {
+        self.loading += 1;
+        self.latest_load += 1;
+        let number = self.latest_load;
+        let sender = self.loaded_channel.0.clone();
+        let max_texture_side = ctx.input(|i| i.max_texture_side);
+        let ctx = ctx.clone();
+        execute(async move {
+            let _ = sender.send((number, load_image(file, is_project, max_texture_side)));
+            ctx.request_repaint();
+        });
+    }
+
+    fn set_image(&mut self, ctx: &egui::Context, loaded: LoadedImage) {
+        let options = self.texture_options();
+        self.texture = Some(TiledTexture::upload(ctx, loaded.chunks, options));
+        self.raw_data = Some(loaded.source.bytes);
+        self.image_dpi = loaded.source.dpi;
+        self.image_vector = loaded.source.vector;
+        self.image_data = Some(loaded.source.image);
+        self.image_name = Some(loaded.name);
+        if let Some(settings) = loaded.settings {
+            self.settings = settings;
+            self.history.record_as("Open project", &self.settings);
+        }
+    }
+
+    fn poster_name(&self) -> &str {
+        self.image_name.as_deref().unwrap_or("poster")
+    }
+
+    /// The export job for the current settings, with the document details filled in.
+    fn job(&self) -> TileJob {
+        let author = self.author.trim();
+        self.settings
+            .job()
+            .with_title(self.image_name.clone())
+            .with_author((!author.is_empty()).then(|| author.to_owned()))
+    }
+
+    /// The page grid for the current settings, with the image fitted as it will print.
+    fn layout(&self) -> TileLayout {
+        let job = self.settings.job();
+        let layout = job.layout();
+        match &self.image_data {
+            Some(data) => {
+                let size = Size::new(data.width() as f32, data.height() as f32);
+                layout.with_image(job.image_area(size))
+            }
+            None => layout,
+        }
+    }
+
+    fn texture_options(&self) -> TextureOptions {
+        if self.show_pixels {
+            TextureOptions::NEAREST
+        } else {
+            TextureOptions::default()
+        }
+    }
+
+    /// Samples the preview texture as `show_pixels` asks.
+    fn update_texture_options(&mut self) {
+        let options = self.texture_options();
+        if let Some(texture) = &mut self.texture {
+            texture.set_options(options);
+        }
+    }
+
+    /// Sets up a line of text in giant letters and loads it as the poster.
+    fn show_banner(&mut self, ui: &mut egui::Ui) {
+        let units = self.settings.units;
+        ui.collapsing("Text banner", |ui| {
+            ui.text_edit_singleline(&mut self.banner.text);
+            egui::Grid::new("banner").num_columns(2).show(ui, |ui| {
+                ui.label("Font");
+                ui.horizontal(|ui| {
+                    let font_name = self
+                        .banner_font
+                        .as_ref()
+                        .map_or("Built-in", |(name, _)| name.as_str());
+                    ui.label(font_name);
+                    if ui.button("choose").clicked() {
+                        let sender = self.font_channel.0.clone();
+                        let open_file = rfd::AsyncFileDialog::new()
+                            .add_filter("Fonts", &["ttf", "otf", "ttc"])
+                            .pick_file();
+                        let ctx = ui.ctx().clone();
+                        execute(async move {
+                            if let Some(file) = open_file.await {
+                                let _ = sender.send((file.file_name(), file.read().await));
+                                ctx.request_repaint();
+                            }
+                        });
+                    }
+                    if self.banner_font.is_some() && ui.button("reset").clicked() {
+                        self.banner_font = None;
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Letter height");
+                ui.add(length_drag_value(
+                    &mut self.banner.letter_height,
+                    units,
+                    None,
+                    self.error_channel.0.clone(),
+                ))
+                .on_hover_text("Height of the capital letters");
+                ui.end_row();
+
+                ui.label("Style");
+                egui::ComboBox::from_id_salt("banner_style")
+                    .selected_text(self.banner.style.name())
+                    .show_ui(ui, |ui| {
+                        for style in LetterStyle::ALL {
+                            ui.selectable_value(&mut self.banner.style, style, style.name());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Outline only saves a lot of ink.");
+                ui.end_row();
+
+                if self.banner.style != LetterStyle::Outlined {
+                    ui.label("Fill");
+                    ui.color_edit_button_srgb(&mut self.banner.fill);
+                    ui.end_row();
+                }
+                if self.banner.style != LetterStyle::Filled {
+                    ui.label("Outline");
+                    ui.horizontal(|ui| {
+                        ui.color_edit_button_srgb(&mut self.banner.stroke);
+                        ui.add(
+                            egui::DragValue::new(&mut self.banner.stroke_width)
+                                .range(0.1..=100.0)
+                                .speed(0.1)
+                                .suffix(" pt"),
                         );
-                        let mut image_start = display_page_start;
-                        let mut image_end = display_page_end;
+                    });
+                    ui.end_row();
+                }
+            });
+            let can_create = !self.banner.text.trim().is_empty();
+            if ui
+                .add_enabled(can_create, egui::Button::new("create"))
+                .on_disabled_hover_text("Type some text first.")
+                .clicked()
+            {
+                self.create_banner(ui.ctx());
+            }
+        });
+    }
 
-                        if y == 0 {
-                            image_start.y += offset_vertical;
+    /// Lists the captions, each with its text, place, size, font and colour.
+    fn show_captions(&mut self, ui: &mut egui::Ui) {
+        let units = self.settings.units;
+        ui.collapsing("Captions", |ui| {
+            let captions_before = self.settings.captions.clone();
+            let mut removed = None;
+            for (index, caption) in self.settings.captions.iter_mut().enumerate() {
+                ui.push_id(index, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::multiline(&mut caption.text).desired_rows(1));
+                        if ui.button("remove").clicked() {
+                            removed = Some(index);
                         }
-                        if x == 0 {
-                            image_start.x += offset_horizontal;
+                    });
+                    egui::Grid::new("caption").num_columns(2).show(ui, |ui| {
+                        ui.label("Position");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                offset_drag_value(
+                                    &mut caption.x,
+                                    units,
+                                    self.error_channel.0.clone(),
+                                )
+                                .range(f64::MIN..=f64::MAX),
+                            )
+                            .on_hover_text("From the poster's left edge");
+                            ui.add(
+                                offset_drag_value(
+                                    &mut caption.y,
+                                    units,
+                                    self.error_channel.0.clone(),
+                                )
+                                .range(f64::MIN..=f64::MAX),
+                            )
+                            .on_hover_text("From the poster's top edge");
+                        });
+                        ui.end_row();
+
+                        ui.label("Size");
+                        ui.horizontal(|ui| {
+                            match &mut caption.size {
+                                TextSize::Points(points) => ui.add(
+                                    egui::DragValue::new(points)
+                                        .range(1.0..=100_000.0)
+                                        .suffix(" pt"),
+                                ),
+                                TextSize::Length(inches) => ui.add(length_drag_value(
+                                    inches,
+                                    units,
+                                    None,
+                                    self.error_channel.0.clone(),
+                                )),
+                            }
+                            .on_hover_text("Font size");
+                            let in_points = matches!(caption.size, TextSize::Points(_));
+                            egui::ComboBox::from_id_salt("size_units")
+                                .width(40.0)
+                                .selected_text(if in_points { "pt" } else { units.suffix() })
+                                .show_ui(ui, |ui| {
+                                    let points = caption.size.points();
+                                    if ui.selectable_label(in_points, "pt").clicked() {
+                                        caption.size = TextSize::Points(points);
+                                    }
+                                    if ui.selectable_label(!in_points, units.suffix()).clicked() {
+                                        caption.size = TextSize::Length(caption.size.inches());
+                                    }
+                                });
+                        });
+                        ui.end_row();
+
+                        ui.label("Font");
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("font")
+                                .selected_text(caption.font.name())
+                                .show_ui(ui, |ui| {
+                                    for font in [CaptionFont::Sans, CaptionFont::Mono] {
+                                        let name = font.name().to_owned();
+                                        ui.selectable_value(&mut caption.font, font, name);
+                                    }
+                                });
+                            if caption.font.is_missing() {
+                                ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                                    .on_hover_text(
+                                        "The font file is not kept between sessions; \
+                                         choose it again.",
+                                    );
+                            }
+                            if ui.button("file").clicked() {
+                                let sender = self.caption_font_channel.0.clone();
+                                let captions = captions_before.clone();
+                                let open_file = rfd::AsyncFileDialog::new()
+                                    .add_filter("Fonts", &["ttf", "otf", "ttc"])
+                                    .pick_file();
+                                let ctx = ui.ctx().clone();
+                                execute(async move {
+                                    if let Some(file) = open_file.await {
+                                        let font = (file.file_name(), file.read().await);
+                                        let _ = sender.send((index, captions, font));
+                                        ctx.request_repaint();
+                                    }
+                                });
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Colour");
+                        ui.color_edit_button_srgb(&mut caption.color);
+                        ui.end_row();
+                    });
+                    ui.separator();
+                });
+            }
+            if let Some(index) = removed {
+                self.settings.captions.remove(index);
+            }
+            if ui.button("add caption").clicked() {
+                self.settings.captions.push(Caption::default());
+            }
+        });
+    }
+
+    /// Adds the font files captions use to egui's fonts, so the preview can show them.
+    fn register_caption_fonts(&mut self, ctx: &egui::Context) {
+        let files: Vec<(String, &Arc<Vec<u8>>)> = self
+            .settings
+            .captions
+            .iter()
+            .filter_map(|caption| match &caption.font {
+                CaptionFont::File { name, data } if !caption.font.is_missing() => {
+                    Some((font_family_name(name, data), data))
+                }
+                _ => None,
+            })
+            .collect();
+        if files
+            .iter()
+            .all(|(family, _)| self.caption_fonts.contains(family))
+        {
+            return;
+        }
+        let mut fonts = egui::FontDefinitions::default();
+        self.caption_fonts.clear();
+        for (family, data) in files {
+            fonts
+                .font_data
+                .insert(family.clone(), egui::FontData::from_owned(data.to_vec()));
+            fonts.families.insert(
+                egui::FontFamily::Name(family.as_str().into()),
+                vec![family.clone()],
+            );
+            self.caption_fonts.push(family);
+        }
+        ctx.set_fonts(fonts);
+    }
+
+    /// Turns the banner into an SVG and loads it, sizing the poster to fit the letters.
+    fn create_banner(&mut self, ctx: &egui::Context) {
+        let font = self
+            .banner_font
+            .as_ref()
+            .map_or(epaint_default_fonts::UBUNTU_LIGHT, |(_, data)| data);
+        match self.banner.to_svg(font) {
+            Ok((svg, size)) => {
+                self.settings.desired_width = size.width;
+                self.settings.desired_height = size.height;
+                self.settings.maintain_aspect_ratio = true;
+                let name: String = self
+                    .banner
+                    .text
+                    .trim()
+                    .chars()
+                    .filter(|c| c.is_alphanumeric() || *c == ' ')
+                    .collect();
+                let name = if name.is_empty() { "banner" } else { &name };
+                self.load_in_background(ctx, (format!("{name}.svg"), svg), false);
+            }
+            Err(err) => self.notifications.push(err.to_string()),
+        }
+    }
+
+    /// Keeps the overlap within half the page, which shrinking the page or
+    /// roll can leave it above.
+    fn clamp_overlap(&mut self) {
+        let max = max_overlap(self.settings.job().layout().page);
+        self.settings.overlap = self.settings.overlap.clamp(0.0, max);
+    }
+
+    /// Sizes the poster and turns the pages as `size_constraint` asks, if it is
+    /// set and can be met. Called every frame, so the size follows the page and overlap.
+    fn apply_size_constraint(&mut self) -> Option<SolvedSize> {
+        let constraint = self.settings.size_constraint?;
+        if self.settings.roll.is_some() {
+            return None;
+        }
+        let data = self.image_data.as_ref()?;
+        let aspect = data.height() as f32 / data.width() as f32;
+        let solved = constraint.solve(
+            aspect,
+            self.settings.page_size.size(),
+            self.settings.overlap,
+        )?;
+        self.settings.desired_width = solved.poster.width;
+        self.settings.desired_height = solved.poster.height;
+        self.settings.landscape = solved.landscape;
+        Some(solved)
+    }
+
+    /// Picks a requirement to size the poster by, instead of its width and height.
+    fn show_size_constraint(&mut self, ui: &mut egui::Ui) {
+        let units = self.settings.units;
+        let current = self.settings.size_constraint;
+        let wall = SizeConstraint::Within {
+            width: self.settings.desired_width,
+            height: self.settings.desired_height,
+        };
+        let choices = [
+            SizeConstraint::MaxPages(12),
+            SizeConstraint::PagesWide(3),
+            SizeConstraint::PagesTall(3),
+            wall,
+        ];
+        egui::ComboBox::from_label("Size by")
+            .selected_text(current.map_or("Width and height", |constraint| constraint.name()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.settings.size_constraint, None, "Width and height");
+                for choice in choices {
+                    let selected = current.is_some_and(|current| {
+                        std::mem::discriminant(&current) == std::mem::discriminant(&choice)
+                    });
+                    if ui.selectable_label(selected, choice.name()).clicked() && !selected {
+                        self.settings.size_constraint = Some(choice);
+                    }
+                }
+            });
+        let Some(constraint) = &mut self.settings.size_constraint else {
+            return;
+        };
+        ui.horizontal(|ui| match constraint {
+            SizeConstraint::MaxPages(pages)
+            | SizeConstraint::PagesWide(pages)
+            | SizeConstraint::PagesTall(pages) => {
+                ui.add(
+                    egui::DragValue::new(pages)
+                        .range(1..=10_000)
+                        .suffix(" pages"),
+                );
+            }
+            SizeConstraint::Within { width, height } => {
+                ui.add(length_drag_value(
+                    width,
+                    units,
+                    None,
+                    self.error_channel.0.clone(),
+                ))
+                .on_hover_text(LENGTH_HINT);
+                ui.label("x");
+                ui.add(length_drag_value(
+                    height,
+                    units,
+                    None,
+                    self.error_channel.0.clone(),
+                ))
+                .on_hover_text(LENGTH_HINT);
+            }
+        });
+        if self.settings.roll.is_some() {
+            ui.label("Not available on roll paper.");
+            return;
+        }
+        match self.apply_size_constraint() {
+            Some(solved) => {
+                let orientation = if solved.landscape {
+                    "landscape"
+                } else {
+                    "portrait"
+                };
+                ui.label(format!(
+                    "{} x {} = {} pages, {orientation}",
+                    solved.columns,
+                    solved.rows,
+                    solved.page_count()
+                ));
+            }
+            None if self.image_data.is_none() => {
+                ui.label("Load an image first.");
+            }
+            None => {
+                ui.colored_label(ui.visuals().warn_fg_color, "No size fits.");
+            }
+        }
+    }
+
+    /// How the image fills a poster of other proportions, and where it is aligned.
+    fn show_fit(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Fit")
+            .selected_text(self.settings.fit.name())
+            .show_ui(ui, |ui| {
+                for fit in Fit::ALL {
+                    ui.selectable_value(&mut self.settings.fit, fit, fit.name());
+                }
+            })
+            .response
+            .on_hover_text(
+                "Stretch distorts the image to the poster. Fit inside shows all of it \
+                 with blank bars; fill and crop covers the poster and cuts off the rest.",
+            );
+        if self.settings.fit == Fit::Stretch {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Align");
+            egui::Grid::new("anchor")
+                .spacing(Vec2::splat(2.0))
+                .show(ui, |ui| {
+                    for (i, anchor) in Anchor::ALL.into_iter().enumerate() {
+                        let selected = self.settings.anchor == anchor;
+                        let symbol = if selected { "●" } else { "○" };
+                        if ui
+                            .selectable_label(selected, symbol)
+                            .on_hover_text(anchor.name())
+                            .clicked()
+                        {
+                            self.settings.anchor = anchor;
                         }
-                        if y == page_count_vertical - 1 {
-                            image_end.y -= offset_vertical;
+                        if i % 3 == 2 {
+                            ui.end_row();
                         }
-                        if x == page_count_horizontal - 1 {
-                            image_end.x -= offset_horizontal;
+                    }
+                });
+        });
+    }
+
+    /// Width, page length and cut marks of the roll paper, when printing on one.
+    fn show_roll(&mut self, ui: &mut egui::Ui) {
+        let units = self.settings.units;
+        let Some(roll) = &mut self.settings.roll else {
+            return;
+        };
+        egui::Grid::new("roll").num_columns(2).show(ui, |ui| {
+            ui.label("Roll width");
+            ui.add(length_drag_value(
+                &mut roll.width,
+                units,
+                None,
+                self.error_channel.0.clone(),
+            ))
+            .on_hover_text(LENGTH_HINT);
+            ui.end_row();
+
+            let mut fixed_length = roll.length.is_some();
+            ui.checkbox(&mut fixed_length, "Page length")
+                .on_hover_text("Split each strip into pages; otherwise it is one continuous page.");
+            if fixed_length {
+                let length = roll.length.get_or_insert(roll.width);
+                ui.add(
+                    length_drag_value(length, units, None, self.error_channel.0.clone())
+                        .range(units.from_inches(0.1)..=units.from_inches(MAX_PAGE_LENGTH)),
+                )
+                .on_hover_text(LENGTH_HINT);
+            } else {
+                roll.length = None;
+                ui.label("Continuous").on_hover_text(format!(
+                    "PDF pages can be at most {:.0} {} long, so longer strips are split.",
+                    units.from_inches(MAX_PAGE_LENGTH),
+                    units.suffix()
+                ));
+            }
+            ui.end_row();
+
+            let mut cut_marks = roll.cut_every.is_some();
+            ui.checkbox(&mut cut_marks, "Cut marks every")
+                .on_hover_text("Drawn in the crop marks layer, along the roll.");
+            if cut_marks {
+                let every = roll.cut_every.get_or_insert(12.0);
+                ui.add(length_drag_value(
+                    every,
+                    units,
+                    None,
+                    self.error_channel.0.clone(),
+                ))
+                .on_hover_text(LENGTH_HINT);
+            } else {
+                roll.cut_every = None;
+            }
+            ui.end_row();
+        });
+        let layout = self.settings.job().layout();
+        let (strips, pages) = if Roll::runs_along_width(layout.poster) {
+            (layout.rows, layout.columns)
+        } else {
+            (layout.columns, layout.rows)
+        };
+        ui.label(format!("Strips: {strips}, pages per strip: {pages}"));
+        if self.settings.roll.is_some_and(|roll| roll.length.is_none()) && pages > 1 {
+            ui.label(format!(
+                "Strips are split every {:.0} {}, the longest page a PDF allows.",
+                units.from_inches(MAX_PAGE_LENGTH),
+                units.suffix()
+            ));
+        }
+    }
+
+    /// Picks the large-format sheet exported next to the pages, if any.
+    fn show_sheet(&mut self, ui: &mut egui::Ui) {
+        let units = self.settings.units;
+        let describe = |sheet: Sheet| {
+            let size = sheet.size();
+            format!(
+                "{} ({:.2}x{:.2} {})",
+                sheet.name(),
+                units.from_inches(size.width),
+                units.from_inches(size.height),
+                units.suffix()
+            )
+        };
+        let is_custom = matches!(self.settings.sheet, Some(Sheet::Custom { .. }));
+        // A new custom sheet starts out as the poster plus an inch all round.
+        let custom = Sheet::Custom {
+            width: self.settings.desired_width + 2.0,
+            height: self.settings.desired_height + 2.0,
+        };
+        egui::ComboBox::from_label("Copy-shop sheet")
+            .selected_text(self.settings.sheet.map_or("None", |sheet| sheet.name()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.settings.sheet, None, "None");
+                for sheet in Sheet::PRESETS {
+                    ui.selectable_value(&mut self.settings.sheet, Some(sheet), describe(sheet));
+                }
+                if ui.selectable_label(is_custom, "Custom").clicked() && !is_custom {
+                    self.settings.sheet = Some(custom);
+                }
+            })
+            .response
+            .on_hover_text(
+                "Also export the whole poster at the same scale on one large sheet, \
+                 for printing at a copy shop.",
+            );
+        if let Some(Sheet::Custom { width, height }) = &mut self.settings.sheet {
+            ui.horizontal(|ui| {
+                ui.add(length_drag_value(
+                    width,
+                    units,
+                    None,
+                    self.error_channel.0.clone(),
+                ))
+                .on_hover_text(LENGTH_HINT);
+                ui.label("x");
+                ui.add(length_drag_value(
+                    height,
+                    units,
+                    None,
+                    self.error_channel.0.clone(),
+                ))
+                .on_hover_text(LENGTH_HINT);
+            });
+        }
+        if let Some(layout) = self.settings.job().sheet_layout() {
+            let orientation = if layout.page.width > layout.page.height {
+                "landscape"
+            } else {
+                "portrait"
+            };
+            match layout.page_count() {
+                1 => ui.label(format!("Fits on one sheet, {orientation}")),
+                sheets => ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Too large for one sheet: needs {sheets}, {orientation}"),
+                ),
+            };
+        }
+    }
+
+    /// Shows the resolution the image will print at, and warns when it is too low.
+    fn show_print_resolution(&mut self, ui: &mut egui::Ui) {
+        let Some(data) = &self.image_data else {
+            return;
+        };
+        if self.image_vector {
+            ui.label("Vector drawing: prints sharp at any size.");
+            return;
+        }
+        let printed = self
+            .settings
+            .job()
+            .image_print_size(data.width(), data.height());
+        let (dpi_x, dpi_y) = effective_dpi(data.width(), data.height(), printed);
+        ui.label(format!("Print resolution: {dpi_x:.0} x {dpi_y:.0} dpi."));
+        if dpi_x.min(dpi_y) < self.min_dpi {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!(
+                    "⚠ Below {:.0} dpi, the print may look blurry or blocky.",
+                    self.min_dpi
+                ),
+            );
+        }
+        ui.horizontal(|ui| {
+            ui.label("Warn below");
+            ui.add(
+                egui::DragValue::new(&mut self.min_dpi)
+                    .range(1.0..=1200.0)
+                    .suffix(" dpi"),
+            );
+        });
+
+        let mut resample = self.settings.resample.is_some();
+        ui.checkbox(&mut resample, "Resample on export")
+            .on_hover_text("Scale the embedded image to a fixed resolution.");
+        match (resample, &mut self.settings.resample) {
+            (true, None) => self.settings.resample = Some(Resample::default()),
+            (false, Some(_)) => self.settings.resample = None,
+            _ => {}
+        }
+        if let Some(resample) = &mut self.settings.resample {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::DragValue::new(&mut resample.dpi)
+                        .range(10.0..=1200.0)
+                        .suffix(" dpi"),
+                );
+                egui::ComboBox::from_id_salt("resample filter")
+                    .selected_text(filter_name(resample.filter))
+                    .show_ui(ui, |ui| {
+                        for filter in [ResampleFilter::Lanczos, ResampleFilter::LineArt] {
+                            ui.selectable_value(&mut resample.filter, filter, filter_name(filter));
                         }
-                        let page_count =
-                            Vec2::new(page_count_horizontal as f32, page_count_vertical as f32);
-                        let page_size = Vec2::new(self.page_size.size().x, self.page_size.size().y);
-                        let desired_size = Vec2::new(self.desired_width, self.desired_height);
-                        let prev_uv = calculate_uv_offset(
-                            Vec2::new(x as f32, y as f32),
-                            page_count,
-                            page_size,
-                            desired_size,
-                        );
-                        let uv = calculate_uv_offset(
-                            Vec2::new(x as f32 + 1.0, y as f32 + 1.0),
-                            page_count,
-                            page_size,
-                            desired_size,
-                        );
-                        if self.texture_id.is_some() {
-                            painter.image(
-                                self.texture_id.unwrap(),
-                                Rect::from_min_max(image_start, image_end)
-                                    .translate(draw_area.min.to_vec2()),
-                                Rect::from_min_max(prev_uv.to_pos2(), uv.to_pos2()),
-                                Color32::WHITE,
-                            );
+                    });
+            });
+            ui.checkbox(&mut resample.shrink_only, "Only shrink")
+                .on_hover_text("Leave images below the target resolution as they are.");
+            match resample.target_pixels(data.width(), data.height(), printed) {
+                Some((width, height)) => ui.label(format!("Exports {width} x {height} pixels.")),
+                None => ui.label("Exports the image unchanged."),
+            };
+        }
+
+        if ui
+            .checkbox(&mut self.show_pixels, "Show pixels in preview")
+            .on_hover_text("Draw image pixels as sharp blocks; zoom to 1:1 to judge the print.")
+            .changed()
+        {
+            self.update_texture_options();
+        }
+    }
+
+    /// Loads files dropped onto the window. Project files open as projects,
+    /// anything else is treated as an image.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input_mut(|i| std::mem::take(&mut i.raw.dropped_files));
+        // Only one image can be shown, so the last file wins.
+        let Some(file) = dropped.into_iter().last() else {
+            return;
+        };
+        // Browsers hand over the contents, native windows only the path.
+        if let Some(bytes) = file.bytes {
+            let sender = if file.name.ends_with(project::EXTENSION) {
+                self.project_channel.0.clone()
+            } else {
+                self.image_channel.0.clone()
+            };
+            let _ = sender.send((file.name, bytes.to_vec()));
+            return;
+        }
+        if let Some(path) = file.path {
+            self.open_path(ctx, path);
+        }
+    }
+
+    /// Reads `path` in the background and opens it like a dropped file: as a
+    /// project if it has the project extension, otherwise as an image.
+    pub fn open_path(&self, ctx: &egui::Context, path: PathBuf) {
+        let is_project = path
+            .extension()
+            .is_some_and(|ext| ext == project::EXTENSION);
+        let sender = if is_project {
+            self.project_channel.0.clone()
+        } else {
+            self.image_channel.0.clone()
+        };
+        let error_sender = self.error_channel.0.clone();
+        let ctx = ctx.clone();
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        execute(async move {
+            match std::fs::read(&path) {
+                Ok(data) => {
+                    let _ = sender.send((name, data));
+                }
+                Err(err) => {
+                    let _ = error_sender.send(err.into());
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Loads an image from the clipboard when the user pastes.
+    ///
+    /// egui turns Ctrl+V or Cmd+V into a paste event only if the clipboard
+    /// holds text, and then swallows the key press. Releasing V with the
+    /// command key held still comes through, so that reads the clipboard
+    /// itself unless the press was already reported as a paste. Browsers only
+    /// let a page read the clipboard inside a paste event, so the web build
+    /// has no "paste" button and uses [`Self::listen_for_pasted_files`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn handle_paste(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (pasted, command, v_released) = ctx.input(|i| {
+            let pasted = i.events.iter().find_map(|event| match event {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            });
+            (pasted, i.modifiers.command, i.key_released(egui::Key::V))
+        });
+        if let Some(text) = pasted {
+            self.paste_image(ctx, Some(text));
+            self.pasted_text = command;
+        }
+        if v_released && !std::mem::take(&mut self.pasted_text) && command {
+            self.paste_image(ctx, None);
+        }
+    }
+
+    /// Sends the clipboard image, or the image file whose path was copied, to
+    /// `image_channel`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn paste_image(&self, ctx: &egui::Context, text: Option<String>) {
+        let sender = self.image_channel.0.clone();
+        let error_sender = self.error_channel.0.clone();
+        let ctx = ctx.clone();
+        execute(async move {
+            match clipboard_image(text) {
+                Ok(Some(file)) => {
+                    let _ = sender.send(file);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    let _ = error_sender.send(err);
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    /// Opens files pasted into the page, such as a copied picture or
+    /// screenshot, like dropped files. egui only passes pasted text on.
+    #[cfg(target_arch = "wasm32")]
+    fn listen_for_pasted_files(&self, ctx: &egui::Context) {
+        use eframe::wasm_bindgen::closure::Closure;
+        use eframe::wasm_bindgen::JsCast as _;
+
+        let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+            return;
+        };
+        let image_sender = self.image_channel.0.clone();
+        let project_sender = self.project_channel.0.clone();
+        let ctx = ctx.clone();
+        let on_paste = Closure::<dyn Fn(web_sys::ClipboardEvent)>::new(
+            move |event: web_sys::ClipboardEvent| {
+                let Some(file) = event
+                    .clipboard_data()
+                    .and_then(|data| data.files())
+                    .and_then(|files| files.get(0))
+                else {
+                    return;
+                };
+                let sender = if file.name().ends_with(project::EXTENSION) {
+                    project_sender.clone()
+                } else {
+                    image_sender.clone()
+                };
+                let ctx = ctx.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let buffer = wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await;
+                    if let Ok(buffer) = buffer {
+                        let data = web_sys::js_sys::Uint8Array::new(&buffer).to_vec();
+                        let _ = sender.send((file.name(), data));
+                        ctx.request_repaint();
+                    }
+                });
+            },
+        );
+        // Registered on the document, where egui listens too: it stops the
+        // event there, and a paste handled by egui may still carry a file.
+        let _ =
+            document.add_event_listener_with_callback("paste", on_paste.as_ref().unchecked_ref());
+        // The listener lives as long as the page.
+        on_paste.forget();
+    }
+
+    fn handle_undo_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // Shift must be checked first: Ctrl+Z also matches while Shift is held.
+        let redo = KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z);
+        let redo_alt = KeyboardShortcut::new(Modifiers::COMMAND, Key::Y);
+        let undo = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+        if ctx.input_mut(|i| i.consume_shortcut(&redo) || i.consume_shortcut(&redo_alt)) {
+            self.redo();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&undo)) {
+            self.undo();
+        }
+    }
+
+    fn undo(&mut self) {
+        if let Some(settings) = self.history.undo() {
+            self.settings = settings.clone();
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(settings) = self.history.redo() {
+            self.settings = settings.clone();
+        }
+    }
+
+    fn show_history(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let undo = ui
+                .add_enabled(self.history.can_undo(), egui::Button::new("undo"))
+                .on_hover_text(
+                    ui.ctx()
+                        .format_shortcut(&KeyboardShortcut::new(Modifiers::COMMAND, Key::Z)),
+                );
+            if undo.clicked() {
+                self.undo();
+            }
+            let redo = ui
+                .add_enabled(self.history.can_redo(), egui::Button::new("redo"))
+                .on_hover_text(ui.ctx().format_shortcut(&KeyboardShortcut::new(
+                    Modifiers::COMMAND | Modifiers::SHIFT,
+                    Key::Z,
+                )));
+            if redo.clicked() {
+                self.redo();
+            }
+        });
+        egui::CollapsingHeader::new("History").show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(160.0)
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    let mut jump = None;
+                    for (index, entry) in self.history.entries().iter().enumerate() {
+                        let undone = index > self.history.current();
+                        let text = if undone {
+                            egui::RichText::new(&entry.label).weak()
+                        } else {
+                            egui::RichText::new(&entry.label)
+                        };
+                        if ui
+                            .selectable_label(index == self.history.current(), text)
+                            .clicked()
+                        {
+                            jump = Some(index);
                         }
                     }
+                    if let Some(index) = jump {
+                        self.settings = self.history.jump_to(index).clone();
+                    }
+                });
+        });
+    }
+
+    fn save_project(&self, ctx: &egui::Context) {
+        let Some(image) = &self.raw_data else {
+            return;
+        };
+        let project = Project {
+            settings: self.settings.clone(),
+            image_dpi: self.image_dpi,
+            name: self.image_name.clone(),
+            image: image.clone(),
+        };
+        let save_file = rfd::AsyncFileDialog::new()
+            .set_title("Save project")
+            .add_filter("etracer project", &[project::EXTENSION])
+            .set_file_name(format!("{}.{}", self.poster_name(), project::EXTENSION))
+            .save_file();
+        let error_sender = self.error_channel.0.clone();
+        let ctx = ctx.clone();
+        execute(async move {
+            if let Some(file) = save_file.await {
+                if let Err(err) = file.write(&project.to_bytes()).await {
+                    let _ = error_sender.send(err.into());
+                    ctx.request_repaint();
                 }
-            });
+            }
         });
     }
+
+    /// Screen points covering one inch of the monitor, for the 1:1 zoom level.
+    fn points_per_inch(&self, ctx: &egui::Context) -> f32 {
+        let native_pixels_per_point = ctx.native_pixels_per_point().unwrap_or(1.0);
+        let screen_dpi = self
+            .screen_dpi
+            .unwrap_or(DEFAULT_SCREEN_DPI * native_pixels_per_point);
+        screen_dpi / ctx.pixels_per_point()
+    }
+
+    fn show_preview_controls(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if self.loading > 0 {
+                ui.spinner();
+                ui.label("Loading image…");
+                ui.separator();
+            }
+            if ui
+                .selectable_label(self.preview.scale.is_none(), "Fit")
+                .on_hover_text("Show every page. Double-click the preview to do the same.")
+                .clicked()
+            {
+                self.preview = PreviewView::default();
+            }
+            let actual_size = self.points_per_inch(ui.ctx());
+            if ui
+                .button("1:1")
+                .on_hover_text("Show the poster at its printed size.")
+                .clicked()
+            {
+                self.preview.scale = Some(actual_size);
+            }
+            if let Some(scale) = self.preview.scale {
+                ui.label(format!(
+                    "{:.0}% of actual size",
+                    scale / actual_size * 100.0
+                ));
+            }
+
+            ui.separator();
+            let mut auto_dpi = self.screen_dpi.is_none();
+            if ui
+                .checkbox(&mut auto_dpi, "Auto screen DPI")
+                .on_hover_text("Untick to enter your monitor's resolution if 1:1 is not life size.")
+                .changed()
+            {
+                self.screen_dpi = (!auto_dpi).then(|| {
+                    DEFAULT_SCREEN_DPI * ui.ctx().native_pixels_per_point().unwrap_or(1.0)
+                });
+            }
+            if let Some(screen_dpi) = &mut self.screen_dpi {
+                ui.add(
+                    egui::DragValue::new(screen_dpi)
+                        .range(30.0..=600.0)
+                        .suffix(" dpi"),
+                );
+            }
+        });
+    }
+
+    fn show_preview(&mut self, ui: &mut egui::Ui) {
+        let (response, painter) =
+            ui.allocate_painter(ui.available_size_before_wrap(), Sense::click_and_drag());
+        let draw_area = response.rect;
+
+        let layout = self.layout();
+        // Pages are drawn apart, with gaps of a fraction of the page size.
+        let gap = 0.05;
+        let pitch = Vec2::new(layout.page.width, layout.page.height) * (1.0 + gap);
+        let grid = Vec2::new(
+            layout.columns as f32 * pitch.x - layout.page.width * gap,
+            layout.rows as f32 * pitch.y - layout.page.height * gap,
+        );
+        let fit_scale = (draw_area.width() / grid.x).min(draw_area.height() / grid.y);
+
+        if response.double_clicked() {
+            self.preview = PreviewView::default();
+        }
+        if response.dragged() {
+            self.preview.scale.get_or_insert(fit_scale);
+            self.preview.pan += response.drag_delta();
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let factor = pinch * (scroll / 200.0).exp();
+            if factor != 1.0 {
+                let old_scale = self.preview.scale.unwrap_or(fit_scale);
+                let new_scale = (old_scale * factor).clamp(fit_scale / 4.0, 2000.0);
+                // Keep the point under the mouse in place.
+                let origin = draw_area.min + self.preview.pan;
+                let new_origin = pointer - (pointer - origin) * (new_scale / old_scale);
+                self.preview.pan = new_origin - draw_area.min;
+                self.preview.scale = Some(new_scale);
+            }
+        }
+
+        // Screen points per inch of paper.
+        let scale = self.preview.scale.unwrap_or(fit_scale);
+        let origin = draw_area.min + self.preview.pan;
+        let page_size = Vec2::new(layout.page.width, layout.page.height) * scale;
+        for tile in layout.tiles() {
+            let page_start =
+                origin + Vec2::new(tile.column as f32 * pitch.x, tile.row as f32 * pitch.y) * scale;
+            let display_page = Rect::from_min_size(page_start, page_size);
+            if !draw_area.intersects(display_page) {
+                continue;
+            }
+            painter.rect_filled(display_page, 2.0, Color32::WHITE);
+
+            if let Some(texture) = &self.texture {
+                let image_rect = Rect::from_min_size(
+                    page_start
+                        + Vec2::new(tile.image.x - tile.page.x, tile.image.y - tile.page.y) * scale,
+                    Vec2::new(tile.image.width, tile.image.height) * scale,
+                );
+                let uv = Rect::from_min_size(
+                    Pos2::new(tile.uv.x, tile.uv.y),
+                    Vec2::new(tile.uv.width, tile.uv.height),
+                );
+                texture.paint(&painter, image_rect, uv);
+            }
+            if !self.settings.captions.is_empty() {
+                let content = Rect::from_min_size(
+                    page_start
+                        + Vec2::new(tile.content.x - tile.page.x, tile.content.y - tile.page.y)
+                            * scale,
+                    Vec2::new(tile.content.width, tile.content.height) * scale,
+                );
+                let painter = painter.with_clip_rect(content.intersect(draw_area));
+                let poster_origin = page_start - Vec2::new(tile.page.x, tile.page.y) * scale;
+                self.paint_captions(&painter, poster_origin, scale);
+            }
+        }
+    }
+
+    /// Draws the captions for the preview, with the poster's top-left corner at
+    /// `origin` and `scale` screen points per inch.
+    fn paint_captions(&self, painter: &egui::Painter, origin: Pos2, scale: f32) {
+        // Glyphs are rasterised at no more than this size and scaled up, so
+        // zooming in does not fill the font atlas.
+        const MAX_LAYOUT_SIZE: f32 = 64.0;
+        let families = painter.ctx().fonts(|fonts| fonts.families());
+        for caption in &self.settings.captions {
+            let family = match &caption.font {
+                CaptionFont::Sans => egui::FontFamily::Proportional,
+                CaptionFont::Mono => egui::FontFamily::Monospace,
+                CaptionFont::File { name, data } => {
+                    let family = egui::FontFamily::Name(font_family_name(name, data).into());
+                    if families.contains(&family) {
+                        family
+                    } else {
+                        egui::FontFamily::Proportional
+                    }
+                }
+            };
+            let size = caption.size.inches() * scale;
+            let layout_size = size.min(MAX_LAYOUT_SIZE);
+            if layout_size < 1.0 {
+                continue;
+            }
+            let [r, g, b] = caption.color;
+            let color = Color32::from_rgb(r, g, b);
+            for (line, baseline) in caption.lines() {
+                let galley = painter.layout_no_wrap(
+                    line.to_owned(),
+                    egui::FontId::new(layout_size, family.clone()),
+                    color,
+                );
+                let ascent = galley
+                    .rows
+                    .first()
+                    .and_then(|row| row.glyphs.first())
+                    .map_or(0.0, |glyph| glyph.pos.y);
+                let zoom = size / layout_size;
+                let corner =
+                    origin + Vec2::new(caption.x, baseline) * scale - Vec2::new(0.0, ascent * zoom);
+                let mut text = egui::Shape::galley(corner, galley, color);
+                // Scale up around the corner, which stays in place.
+                text.transform(egui::emath::TSTransform::new(
+                    corner.to_vec2() * (1.0 - zoom),
+                    zoom,
+                ));
+                painter.add(text);
+            }
+        }
+    }
+
+    fn show_notifications(&mut self, ctx: &egui::Context) {
+        if self.notifications.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, Vec2::new(-8.0, -8.0))
+            .show(ctx, |ui| {
+                let mut dismissed = None;
+                for (i, message) in self.notifications.iter().enumerate() {
+                    Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(ui.visuals().error_fg_color, message);
+                            if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                dismissed = Some(i);
+                            }
+                        });
+                    });
+                }
+                if let Some(i) = dismissed {
+                    self.notifications.remove(i);
+                }
+            });
+    }
+}
+
+/// Darkens the window while files are dragged over it.
+fn show_drop_hint(ctx: &egui::Context) {
+    if ctx.input(|i| i.raw.hovered_files.is_empty()) {
+        return;
+    }
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("drop hint"),
+    ));
+    let screen = ctx.screen_rect();
+    painter.rect_filled(screen, 0.0, Color32::from_black_alpha(192));
+    painter.text(
+        screen.center(),
+        egui::Align2::CENTER_CENTER,
+        "Drop an image or project to load it",
+        egui::TextStyle::Heading.resolve(&ctx.style()),
+        Color32::WHITE,
+    );
+}
+
+/// Reads an image off the clipboard as PNG bytes. When it holds no image,
+/// `text` is tried as the path of an image file, as file managers copy them.
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_image(text: Option<String>) -> Result<Option<FileData>, Error> {
+    let mut clipboard = match arboard::Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(err) => return Err(clipboard_error(err)),
+    };
+    match clipboard.get_image() {
+        Ok(image) => {
+            let pixels = image::RgbaImage::from_raw(
+                image.width as u32,
+                image.height as u32,
+                image.bytes.into_owned(),
+            )
+            .ok_or_else(|| Error::Io(std::io::Error::other("clipboard image is malformed")))?;
+            let image = image::DynamicImage::ImageRgba8(pixels);
+            let data = crate::source::encode(&image, image::ImageFormat::Png)?;
+            return Ok(Some(("Pasted image.png".to_owned(), data)));
+        }
+        Err(arboard::Error::ContentNotAvailable) => {}
+        Err(err) => return Err(clipboard_error(err)),
+    }
+
+    let text = match text {
+        Some(text) => text,
+        None => clipboard.get_text().unwrap_or_default(),
+    };
+    let path = text.lines().next().unwrap_or_default().trim();
+    let path = std::path::Path::new(path.strip_prefix("file://").unwrap_or(path));
+    if path.as_os_str().is_empty() || !path.is_file() {
+        return Ok(None);
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(Some((name.into_owned(), std::fs::read(path)?)))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_error(err: arboard::Error) -> Error {
+    Error::Io(std::io::Error::other(err))
+}
+
+fn filter_name(filter: ResampleFilter) -> &'static str {
+    match filter {
+        ResampleFilter::Lanczos => "Photo (Lanczos)",
+        ResampleFilter::LineArt => "Line art",
+    }
+}
+
+/// An editor for a length stored in inches and shown in `units`.
+///
+/// Typed text may use any unit [`parse_length`] understands. Percentages are of
+/// `native`, the image's size in inches. Text that cannot be used, including a
+/// percentage when the native size is unknown, is reported on `errors`.
+fn length_drag_value(
+    inches: &mut f32,
+    units: Units,
+    native: Option<f32>,
+    errors: Sender<Error>,
+) -> egui::DragValue<'_> {
+    length_editor(inches, units, native, errors, parse_length)
+}
+
+/// Like [`length_drag_value`], but for offsets and overlaps, which may be zero
+/// or negative as far as the range set by the caller allows.
+fn offset_drag_value(inches: &mut f32, units: Units, errors: Sender<Error>) -> egui::DragValue<'_> {
+    length_editor(inches, units, None, errors, parse_signed_length)
+}
+
+fn length_editor(
+    inches: &mut f32,
+    units: Units,
+    native: Option<f32>,
+    errors: Sender<Error>,
+    parse: fn(&str, Units) -> Result<LengthInput, ParseLengthError>,
+) -> egui::DragValue<'_> {
+    egui::DragValue::from_get_set(move |value| {
+        if let Some(value) = value {
+            *inches = units.to_inches(value as f32);
+        }
+        units.from_inches(*inches) as f64
+    })
+    .speed(units.from_inches(0.05))
+    .range(units.from_inches(0.1)..=units.from_inches(10_000.0))
+    .max_decimals(2)
+    .suffix(format!(" {}", units.suffix()))
+    // Parse only the finished text, so half-typed units are not reported.
+    .update_while_editing(false)
+    .custom_parser(move |text| {
+        let inches = parse(text, units).and_then(|length| match length {
+            LengthInput::Inches(inches) => Ok(inches),
+            LengthInput::Percent(percent) => native
+                .map(|native| native * percent / 100.0)
+                .ok_or_else(|| ParseLengthError::NoNativeSize(text.trim().to_owned())),
+        });
+        match inches {
+            Ok(inches) => Some(units.from_inches(inches) as f64),
+            Err(err) => {
+                let _ = errors.send(err.into());
+                None
+            }
+        }
+    })
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -420,6 +1952,43 @@ fn execute<F: Future<Output = ()> + 'static>(f: F) {
     wasm_bindgen_futures::spawn_local(f);
 }
 
+/// Like `execute`, but the future is created on the thread that runs it, so it
+/// does not have to be `Send` (krilla documents are not).
+#[cfg(not(target_arch = "wasm32"))]
+fn execute_local<F, Fut>(make_future: F)
+where
+    F: FnOnce() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    std::thread::spawn(move || futures::executor::block_on(make_future()));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn execute_local<F, Fut>(make_future: F)
+where
+    F: FnOnce() -> Fut + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    wasm_bindgen_futures::spawn_local(make_future());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn yield_now() {
+    // Natively the export runs on its own thread, so there is nothing to yield to.
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn yield_now() {
+    // Hand control back to the browser so it can repaint the progress bar.
+    let promise = web_sys::js_sys::Promise::new(&mut |resolve, _reject| {
+        web_sys::window()
+            .expect("Window not found")
+            .set_timeout_with_callback(&resolve)
+            .expect("Failed to schedule timeout");
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
 #[cfg(target_arch = "wasm32")]
 fn open_file_picker() {
     let window = web_sys::window().expect("Window not found");
@@ -449,83 +2018,62 @@ fn open_file_picker() {
     });
 }
 
-fn calculate_page_count(desired: f32, print: f32) -> i32 {
-    (desired / print).ceil() as i32
-}
-
-fn calculate_image_scale(desired: f32, print: f32, pdf_page: f32) -> f32 {
-    (desired / print) * pdf_page
-}
-
-fn calculate_uv_offset(page: Vec2, page_count: Vec2, page_size: Vec2, desired_size: Vec2) -> Vec2 {
-    let page_offset = (page_count * page_size - desired_size) / 2.0;
-    let uv = (page_size * page - page_offset) / desired_size;
-    uv.clamp(Vec2::ZERO, Vec2::new(1.0, 1.0))
-}
-
-#[derive(Debug)]
-pub struct ParseImageError;
+/// Writes the tiled PDF to `file`, then asks where to put the copy-shop sheet
+/// if the job has one.
+async fn save_pdfs(
+    file: rfd::FileHandle,
+    job: &TileJob,
+    data: &[u8],
+    name: &str,
+    progress: &Sender<ExportMessage>,
+    cancel: &AtomicBool,
+    ctx: &egui::Context,
+) -> Result<(), Error> {
+    let Some(pdf) = export_pdf(PdfExport::new(job, data)?, progress, cancel, ctx).await? else {
+        return Ok(());
+    };
+    file.write(&pdf).await?;
 
-impl std::error::Error for ParseImageError {}
-
-impl std::fmt::Display for ParseImageError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Failed to parse image. Must be [png, jpeg].")
+    let Some(sheet) = job.sheet() else {
+        return Ok(());
+    };
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .set_title("Save copy-shop PDF")
+        .set_file_name(format!("{name}-{}.pdf", sheet.name().to_ascii_lowercase()))
+        .save_file()
+        .await
+    else {
+        return Ok(());
+    };
+    if let Some(export) = PdfExport::for_sheet(job, data)? {
+        if let Some(pdf) = export_pdf(export, progress, cancel, ctx).await? {
+            file.write(&pdf).await?;
+        }
     }
+    Ok(())
 }
 
-fn parse_krilla_image(data: &[u8]) -> Result<krilla::image::Image, ParseImageError> {
-    if let Some(png) = krilla::image::Image::from_png(data) {
-        return Ok(png);
-    } 
-    if let Some(jpeg) = krilla::image::Image::from_jpeg(data) {
-        return Ok(jpeg);
-    } 
-    return Err(ParseImageError);
-}
-
-fn generate_pdf(
-    desired_width: f32,
-    desired_height: f32,
-    page_size: Page,
-    image_data: &[u8],
-) -> Vec<u8> {
-    let krilla_image = parse_krilla_image(image_data).expect("Only png and jpeg should be supported.");
-    let page_width = page_size.size().x;
-    let page_height = page_size.size().y;
-    let dpi = 72.0;
-    let pdf_point_page_width = page_width * dpi; //595;
-    let pdf_point_page_height = page_height * dpi; //842;
-
-    let page_count_horizontal = calculate_page_count(desired_width, page_width);
-    let page_count_vertical = calculate_page_count(desired_height, page_height);
-
-    let desired_image_width =
-        calculate_image_scale(desired_width, page_width, pdf_point_page_width);
-    let desired_image_height =
-        calculate_image_scale(desired_height, page_height, pdf_point_page_height);
-
-    let mut doc = krilla::Document::new();
-    for y in 0..page_count_vertical {
-        let y_offset =
-            (((page_count_vertical as f32 * pdf_point_page_height) - desired_image_height) / 2.0)
-                - (y as f32 * pdf_point_page_height);
-        for x in 0..page_count_horizontal {
-            let x_offset = (((page_count_horizontal as f32 * pdf_point_page_width)
-                - desired_image_width)
-                / 2.0)
-                - (x as f32 * pdf_point_page_width);
-            let mut page = doc.start_page_with(PageSettings::new(pdf_point_page_width, pdf_point_page_height));
-            let mut surface = page.surface();
-            surface.push_transform(&krilla::geom::Transform::from_translate(x_offset, y_offset));
-            surface.draw_image(
-                krilla_image.clone(),
-                krilla::geom::Size::from_wh(desired_image_width, desired_image_height).unwrap(),
-            );
-            surface.pop();
-            surface.finish();
-            page.finish();
+/// Writes the pages of `export` one by one, reporting progress over `progress`.
+///
+/// Returns `Ok(None)` if `cancel` was set before the last page was written.
+async fn export_pdf(
+    mut export: PdfExport,
+    progress: &Sender<ExportMessage>,
+    cancel: &AtomicBool,
+    ctx: &egui::Context,
+) -> Result<Option<Vec<u8>>, Error> {
+    let total = export.total_pages();
+    let _ = progress.send(ExportMessage::Progress { done: 0, total });
+    while export.write_next_page()? {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(None);
         }
+        let _ = progress.send(ExportMessage::Progress {
+            done: export.pages_done(),
+            total,
+        });
+        ctx.request_repaint();
+        yield_now().await;
     }
-    doc.finish().unwrap()
+    export.finish().map(Some)
 }
@@ -0,0 +1,207 @@
+//! Giant-letter banners: a line of text set in a chosen font and turned into an
+//! SVG of plain paths, which is then tiled like any other drawing.
+
+use std::fmt::Write;
+use std::sync::Arc;
+
+use resvg::tiny_skia::{Path, PathBuilder, PathSegment};
+use resvg::usvg::{self, fontdb, Group, Node};
+
+use crate::error::Error;
+use crate::layout::Size;
+
+/// SVG user units per inch. usvg converts physical units at this resolution.
+const UNITS_PER_INCH: f32 = 96.0;
+
+/// How the letters are painted.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+pub enum LetterStyle {
+    Filled,
+    /// Only the outlines, which saves a lot of ink on large letters.
+    Outlined,
+    FilledAndOutlined,
+}
+
+impl LetterStyle {
+    pub const ALL: [LetterStyle; 3] = [
+        LetterStyle::Filled,
+        LetterStyle::Outlined,
+        LetterStyle::FilledAndOutlined,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LetterStyle::Filled => "Filled",
+            LetterStyle::Outlined => "Outline only",
+            LetterStyle::FilledAndOutlined => "Filled with outline",
+        }
+    }
+
+    fn fills(self) -> bool {
+        self != LetterStyle::Outlined
+    }
+
+    fn strokes(self) -> bool {
+        self != LetterStyle::Filled
+    }
+}
+
+/// A line of text to print in letters spanning many pages.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TextBanner {
+    pub text: String,
+    /// Height of the capital letters, in inches.
+    pub letter_height: f32,
+    pub style: LetterStyle,
+    pub fill: [u8; 3],
+    pub stroke: [u8; 3],
+    /// Width of the outline, in points.
+    pub stroke_width: f32,
+}
+
+impl Default for TextBanner {
+    fn default() -> Self {
+        Self {
+            text: "HAPPY BIRTHDAY".to_owned(),
+            letter_height: 8.0,
+            style: LetterStyle::Filled,
+            fill: [0, 0, 0],
+            stroke: [0, 0, 0],
+            stroke_width: 2.0,
+        }
+    }
+}
+
+impl TextBanner {
+    /// Sets the text in `font`, a TrueType or OpenType file, and returns an SVG
+    /// of the letters as paths together with its size in inches.
+    ///
+    /// The SVG needs no fonts to display, so it tiles and previews like any
+    /// other SVG file.
+    pub fn to_svg(&self, font: &[u8]) -> Result<(Vec<u8>, Size), Error> {
+        let mut fonts = fontdb::Database::new();
+        fonts.load_font_data(font.to_vec());
+        let family = fonts
+            .faces()
+            .next()
+            .and_then(|face| face.families.first())
+            .map(|(name, _)| name.clone())
+            .ok_or_else(|| invalid("the font file could not be read"))?;
+        let options = usvg::Options {
+            fontdb: Arc::new(fonts),
+            font_family: family,
+            ..usvg::Options::default()
+        };
+
+        // The letter height is that of a capital H, measured large for precision.
+        let reference = 1000.0;
+        let cap_height = outline("H", reference, &options)?
+            .compute_tight_bounds()
+            .map(|bounds| bounds.height() / reference)
+            .filter(|height| *height > 0.0)
+            .ok_or_else(|| invalid("the font has no capital H to measure"))?;
+        let font_size = self.letter_height * UNITS_PER_INCH / cap_height;
+        let letters = outline(&self.text, font_size, &options)?;
+
+        let stroke_width = self.stroke_width * UNITS_PER_INCH / 72.0;
+        let padding = if self.style.strokes() {
+            stroke_width / 2.0
+        } else {
+            0.0
+        };
+        let bounds = letters
+            .compute_tight_bounds()
+            .unwrap_or_else(|| letters.bounds());
+        let (x, y) = (bounds.left() - padding, bounds.top() - padding);
+        let width = bounds.width() + 2.0 * padding;
+        let height = bounds.height() + 2.0 * padding;
+
+        let color = |[r, g, b]: [u8; 3]| format!("#{r:02x}{g:02x}{b:02x}");
+        let fill = if self.style.fills() {
+            color(self.fill)
+        } else {
+            "none".to_owned()
+        };
+        let stroke = if self.style.strokes() {
+            format!(
+                r#" stroke="{}" stroke-width="{stroke_width:.3}" stroke-linejoin="round""#,
+                color(self.stroke)
+            )
+        } else {
+            String::new()
+        };
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.4}in" height="{:.4}in" viewBox="{x:.3} {y:.3} {width:.3} {height:.3}">
+<path fill="{fill}"{stroke} d="{}"/>
+</svg>
+"#,
+            width / UNITS_PER_INCH,
+            height / UNITS_PER_INCH,
+            path_data(&letters)
+        );
+        Ok((
+            svg.into_bytes(),
+            Size::new(width / UNITS_PER_INCH, height / UNITS_PER_INCH),
+        ))
+    }
+}
+
+/// The outlines of `text` set in the default font of `options`, in SVG user units.
+fn outline(text: &str, font_size: f32, options: &usvg::Options<'_>) -> Result<Path, Error> {
+    let text = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"><text font-size="{font_size}" xml:space="preserve">{text}</text></svg>"#
+    );
+    let tree = usvg::Tree::from_data(svg.as_bytes(), options)
+        .map_err(|err| invalid(&format!("the text could not be set: {err}")))?;
+    let mut builder = PathBuilder::new();
+    collect_paths(tree.root(), &mut builder);
+    builder
+        .finish()
+        .ok_or_else(|| invalid("nothing to draw; the font may lack these letters"))
+}
+
+/// Appends every path under `group` to `builder`, in absolute coordinates.
+fn collect_paths(group: &Group, builder: &mut PathBuilder) {
+    for node in group.children() {
+        match node {
+            Node::Group(group) => collect_paths(group, builder),
+            Node::Text(text) => collect_paths(text.flattened(), builder),
+            Node::Path(path) => {
+                if let Some(data) = path.data().clone().transform(path.abs_transform()) {
+                    builder.push_path(&data);
+                }
+            }
+            Node::Image(_) => {}
+        }
+    }
+}
+
+/// `path` as the `d` attribute of an SVG path.
+fn path_data(path: &Path) -> String {
+    let mut data = String::new();
+    for segment in path.segments() {
+        let _ = match segment {
+            PathSegment::MoveTo(p) => write!(data, "M{:.3} {:.3}", p.x, p.y),
+            PathSegment::LineTo(p) => write!(data, "L{:.3} {:.3}", p.x, p.y),
+            PathSegment::QuadTo(c, p) => {
+                write!(data, "Q{:.3} {:.3} {:.3} {:.3}", c.x, c.y, p.x, p.y)
+            }
+            PathSegment::CubicTo(c1, c2, p) => write!(
+                data,
+                "C{:.3} {:.3} {:.3} {:.3} {:.3} {:.3}",
+                c1.x, c1.y, c2.x, c2.y, p.x, p.y
+            ),
+            PathSegment::Close => write!(data, "Z"),
+        };
+    }
+    data
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidBanner(reason.to_owned())
+}
@@ -0,0 +1,122 @@
+//! Text placed on the poster: titles, scale notes, copyright lines.
+//!
+//! Captions are positioned in inches from the poster's top-left corner and
+//! drawn on every page they reach, so a caption across a seam continues on the
+//! neighbouring page like the image does.
+
+use std::sync::Arc;
+
+use krilla::font::Font;
+
+use crate::error::Error;
+use crate::export::POINTS_PER_INCH;
+
+/// Distance between the baselines of neighbouring lines, as a multiple of the font size.
+pub const LINE_HEIGHT: f32 = 1.2;
+
+/// The typeface of a caption.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
+pub enum CaptionFont {
+    /// Ubuntu Light, the font the app itself uses.
+    Sans,
+    /// Hack, a monospaced font.
+    Mono,
+    /// A TrueType or OpenType file chosen by the user.
+    ///
+    /// Only the name is serialised, so saved app state stays small; project
+    /// files embed the data separately. Empty data means the file must be
+    /// chosen again.
+    File {
+        name: String,
+        #[serde(skip)]
+        data: Arc<Vec<u8>>,
+    },
+}
+
+impl CaptionFont {
+    pub fn name(&self) -> &str {
+        match self {
+            CaptionFont::Sans => "Sans",
+            CaptionFont::Mono => "Monospace",
+            CaptionFont::File { name, .. } => name,
+        }
+    }
+
+    /// Whether this is a font file whose data was not restored.
+    pub fn is_missing(&self) -> bool {
+        matches!(self, CaptionFont::File { data, .. } if data.is_empty())
+    }
+
+    /// The font for krilla, or an error if the file is not a font it can read.
+    pub fn load(&self) -> Result<Font, Error> {
+        let data: Arc<dyn AsRef<[u8]> + Send + Sync> = match self {
+            CaptionFont::Sans => Arc::new(epaint_default_fonts::UBUNTU_LIGHT),
+            CaptionFont::Mono => Arc::new(epaint_default_fonts::HACK_REGULAR),
+            CaptionFont::File { data, .. } => data.clone(),
+        };
+        Font::new(data, 0, vec![]).ok_or_else(|| Error::InvalidFont(self.name().to_owned()))
+    }
+}
+
+/// How large a caption's letters are.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+pub enum TextSize {
+    /// A font size in points, as in a word processor.
+    Points(f32),
+    /// The font size as a length on the poster, in inches.
+    Length(f32),
+}
+
+impl TextSize {
+    pub fn points(self) -> f32 {
+        match self {
+            TextSize::Points(points) => points,
+            TextSize::Length(inches) => inches * POINTS_PER_INCH,
+        }
+    }
+
+    pub fn inches(self) -> f32 {
+        self.points() / POINTS_PER_INCH
+    }
+}
+
+/// A block of text at a fixed place on the poster.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Caption {
+    /// The text, with a line break between lines.
+    pub text: String,
+    /// Left edge of the text, in inches from the poster's left edge.
+    pub x: f32,
+    /// Top of the text, in inches from the poster's top edge. The first
+    /// baseline sits one font size below.
+    pub y: f32,
+    pub size: TextSize,
+    pub font: CaptionFont,
+    pub color: [u8; 3],
+}
+
+impl Default for Caption {
+    fn default() -> Self {
+        Self {
+            text: "Caption".to_owned(),
+            x: 1.0,
+            y: 1.0,
+            size: TextSize::Points(72.0),
+            font: CaptionFont::Sans,
+            color: [0, 0, 0],
+        }
+    }
+}
+
+impl Caption {
+    /// Each line of the text with the position of its baseline, in inches
+    /// from the poster's top edge.
+    pub fn lines(&self) -> impl Iterator<Item = (&str, f32)> + '_ {
+        let size = self.size.inches();
+        self.text
+            .lines()
+            .enumerate()
+            .map(move |(i, line)| (line, self.y + size * (1.0 + i as f32 * LINE_HEIGHT)))
+    }
+}
@@ -0,0 +1,638 @@
+//! Headless command-line interface, for tiling images without opening the GUI.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::export::{generate_pdf, generate_sheet_pdf, TileJob};
+use crate::layout::{self, Anchor, Fit, LengthInput, Page, Roll, Sheet, SizeConstraint, Units};
+use crate::overlay::{Overlay, Overlays};
+use crate::resample::{Resample, ResampleFilter};
+use crate::source::SourceImage;
+
+const USAGE: &str = "\
+Usage: etracer tile <INPUT>... [OPTIONS]
+       etracer [FILE]    Open the window, with FILE if given
+
+Tiles each INPUT image across printable pages and writes one PDF per image.
+INPUT may be a file, a directory (every png, jpeg or svg inside is tiled) or
+a glob pattern such as 'scans/*.png'.
+
+Options:
+  -w, --width <LENGTH>     Poster width, e.g. 120cm, 1.2m, 47in or '47 1/4in'
+  -h, --height <LENGTH>    Poster height; when only one side is given the
+                           other follows the image's aspect ratio
+      --fit <MODE>         With both --width and --height, how an image of other
+                           proportions fills the poster: stretch (default),
+                           contain (whole image, blank bars) or cover (fill,
+                           cropping the image)
+      --align <POINT>      Where contain and cover align the image: center
+                           (default), top, bottom, left, right, top-left,
+                           top-right, bottom-left or bottom-right
+      --max-pages <N>      Instead of a size, make the poster as large as fits
+                           on at most N pages, turning them if that helps
+      --pages-wide <N>     Instead of a size, fill exactly N pages across
+      --pages-tall <N>     Instead of a size, fill exactly N pages down
+      --wall <SIZE>        Instead of a size, make the poster as large as fits
+                           a wall or frame of this size, e.g. 2mx1.5m
+  -p, --page <PAGE>        letter (default), a4, legal or tabloid
+      --landscape          Turn the pages to landscape
+      --roll <WIDTH>       Print on roll paper of this width instead of pages,
+                           in strips along the poster's longer side
+      --roll-length <LENGTH>
+                           Split each strip into pages of this length, at
+                           most 200in (default: one continuous page, split
+                           every 200in, the longest page a PDF allows)
+      --cut-every <LENGTH> Cut marks along the roll at this interval
+      --overlap <LENGTH>   Strip shared by neighbouring pages (default 0)
+      --dpi <DPI>          Resample the image to this print resolution
+      --max-dpi <DPI>      Only shrink images printing above this resolution
+      --line-art           Resample with sharp edges, for logos and drawings
+      --crop-tiles         Embed only the part of the image each page shows
+      --reminder           Remind on every page to print at 100%
+      --overlays <LIST>    Printing aids, each in its own PDF layer: a comma
+                           separated list of crop, labels, grid, ruler and
+                           outline, or all
+      --sheet <SHEET>      Also write the poster on one large-format sheet for
+                           a copy shop: a0, a1 or a size such as 90cmx2m
+      --title <TEXT>       PDF title (default: the input's file name)
+      --author <TEXT>      PDF author
+  -o, --output <PATH>      Output PDF, or output directory for several inputs
+      --help               Print this help
+
+Without --width or --height the embedded resolution decides the size.
+Lengths may be in in, ft, pt, mm, cm or m; without a unit they are in inches.
+
+Exit codes: 0 success, 1 at least one image failed, 2 invalid arguments.";
+
+/// Exit code when every image was tiled.
+pub const EXIT_SUCCESS: i32 = 0;
+/// Exit code when at least one image could not be tiled.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for malformed command lines.
+pub const EXIT_USAGE: i32 = 2;
+
+struct Options {
+    inputs: Vec<OsString>,
+    width: Option<f32>,
+    height: Option<f32>,
+    fit: Fit,
+    anchor: Anchor,
+    size_constraint: Option<SizeConstraint>,
+    page: Page,
+    landscape: bool,
+    roll: Option<Roll>,
+    roll_length: Option<f32>,
+    cut_every: Option<f32>,
+    overlap: f32,
+    resample: Option<Resample>,
+    line_art: bool,
+    crop_tiles: bool,
+    print_reminder: bool,
+    overlays: Overlays,
+    sheet: Option<Sheet>,
+    title: Option<String>,
+    author: Option<String>,
+    output: Option<PathBuf>,
+}
+
+/// Whether a program started with `first` as its first argument is meant for
+/// the command line rather than the GUI, which opens a file given on its own.
+pub fn is_command(first: &str) -> bool {
+    first == "tile" || first == "help" || first.starts_with('-')
+}
+
+/// Runs the command line in `args` (without the program name) and returns the exit code.
+///
+/// Arguments are taken as the OS passes them, so input and output paths need
+/// not be valid UTF-8.
+pub fn run(args: impl IntoIterator<Item = OsString>) -> i32 {
+    let mut args = args.into_iter();
+    let command = args.next().map(|arg| arg.to_string_lossy().into_owned());
+    match command.as_deref() {
+        Some("tile") => {}
+        Some("--help" | "help") => {
+            println!("{USAGE}");
+            return EXIT_SUCCESS;
+        }
+        Some(other) => {
+            eprintln!("error: unknown command '{other}'\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+        None => {
+            eprintln!("{USAGE}");
+            return EXIT_USAGE;
+        }
+    }
+    let options = match parse_options(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return EXIT_SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return EXIT_USAGE;
+        }
+    };
+    let inputs = match expand_inputs(&options.inputs) {
+        Ok(inputs) => inputs,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return EXIT_USAGE;
+        }
+    };
+
+    let outputs = match output_paths(&inputs, options.output.as_deref()) {
+        Ok(outputs) => outputs,
+        Err(message) => {
+            eprintln!("error: {message}");
+            return EXIT_USAGE;
+        }
+    };
+    if inputs.len() > 1 {
+        if let Some(dir) = &options.output {
+            if let Err(err) = std::fs::create_dir_all(dir) {
+                eprintln!("error: cannot create {}: {err}", dir.display());
+                return EXIT_FAILURE;
+            }
+        }
+    }
+    let mut exit_code = EXIT_SUCCESS;
+    for (input, output) in inputs.iter().zip(&outputs) {
+        match tile(input, output, &options) {
+            Ok(written) => {
+                for (output, columns, rows) in written {
+                    println!(
+                        "{} -> {} ({columns} x {rows} pages)",
+                        input.display(),
+                        output.display()
+                    );
+                }
+            }
+            Err(message) => {
+                eprintln!("error: {}: {message}", input.display());
+                exit_code = EXIT_FAILURE;
+            }
+        }
+    }
+    exit_code
+}
+
+/// Parses the arguments after `tile`. Returns `Ok(None)` if help was requested.
+fn parse_options(mut args: impl Iterator<Item = OsString>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        inputs: Vec::new(),
+        width: None,
+        height: None,
+        fit: Fit::Stretch,
+        anchor: Anchor::Center,
+        size_constraint: None,
+        page: Page::Letter,
+        landscape: false,
+        roll: None,
+        roll_length: None,
+        cut_every: None,
+        overlap: 0.0,
+        resample: None,
+        line_art: false,
+        crop_tiles: false,
+        print_reminder: false,
+        overlays: Overlays::default(),
+        sheet: None,
+        title: None,
+        author: None,
+        output: None,
+    };
+    while let Some(arg) = args.next() {
+        // Only inputs and the output may be paths that are not valid UTF-8.
+        let arg = match arg.into_string() {
+            Ok(arg) => arg,
+            Err(input) => {
+                options.inputs.push(input);
+                continue;
+            }
+        };
+        let mut value = |name: &str| {
+            let value = args.next().ok_or_else(|| format!("{name} needs a value"))?;
+            value
+                .into_string()
+                .map_err(|value| format!("{name}: '{}' is not valid text", value.to_string_lossy()))
+        };
+        match arg.as_str() {
+            "--help" => return Ok(None),
+            "-w" | "--width" => options.width = Some(parse_length(&value(&arg)?)?),
+            "-h" | "--height" => options.height = Some(parse_length(&value(&arg)?)?),
+            "--fit" => options.fit = parse_fit(&value(&arg)?)?,
+            "--align" => options.anchor = parse_anchor(&value(&arg)?)?,
+            "--max-pages" => {
+                options.size_constraint =
+                    Some(SizeConstraint::MaxPages(parse_count(&value(&arg)?)?))
+            }
+            "--pages-wide" => {
+                options.size_constraint =
+                    Some(SizeConstraint::PagesWide(parse_count(&value(&arg)?)?))
+            }
+            "--pages-tall" => {
+                options.size_constraint =
+                    Some(SizeConstraint::PagesTall(parse_count(&value(&arg)?)?))
+            }
+            "--wall" => {
+                let (width, height) = parse_dimensions(&value(&arg)?)?;
+                options.size_constraint = Some(SizeConstraint::Within { width, height });
+            }
+            "-p" | "--page" => options.page = parse_page(&value(&arg)?)?,
+            "--landscape" => options.landscape = true,
+            "--roll" => {
+                options.roll = Some(Roll {
+                    width: parse_length(&value(&arg)?)?,
+                    ..Roll::default()
+                })
+            }
+            "--roll-length" => options.roll_length = Some(parse_length(&value(&arg)?)?),
+            "--cut-every" => options.cut_every = Some(parse_length(&value(&arg)?)?),
+            "--overlap" => options.overlap = parse_overlap(&value(&arg)?)?,
+            "--dpi" | "--max-dpi" => {
+                options.resample = Some(Resample {
+                    dpi: parse_dpi(&value(&arg)?)?,
+                    shrink_only: arg == "--max-dpi",
+                    ..Resample::default()
+                })
+            }
+            "--line-art" => options.line_art = true,
+            "--crop-tiles" => options.crop_tiles = true,
+            "--reminder" => options.print_reminder = true,
+            "--overlays" => options.overlays = parse_overlays(&value(&arg)?)?,
+            "--sheet" => options.sheet = Some(parse_sheet(&value(&arg)?)?),
+            "--title" => options.title = Some(value(&arg)?),
+            "--author" => options.author = Some(value(&arg)?),
+            "-o" | "--output" => {
+                let output = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+                options.output = Some(PathBuf::from(output));
+            }
+            flag if flag.starts_with('-') => return Err(format!("unknown option '{flag}'")),
+            _ => options.inputs.push(arg.into()),
+        }
+    }
+    if options.inputs.is_empty() {
+        return Err("no input images given".to_owned());
+    }
+    if options.size_constraint.is_some() {
+        if options.width.is_some() || options.height.is_some() {
+            return Err(
+                "--max-pages, --pages-wide, --pages-tall and --wall replace --width and --height"
+                    .to_owned(),
+            );
+        }
+        if options.roll.is_some() {
+            return Err(
+                "--max-pages, --pages-wide, --pages-tall and --wall need cut pages, not --roll"
+                    .to_owned(),
+            );
+        }
+    }
+    if let Some(resample) = &mut options.resample {
+        if options.line_art {
+            resample.filter = ResampleFilter::LineArt;
+        }
+    } else if options.line_art {
+        return Err("--line-art needs --dpi or --max-dpi".to_owned());
+    }
+    let page = match &mut options.roll {
+        Some(roll) => {
+            roll.length = options.roll_length;
+            roll.cut_every = options.cut_every;
+            if [Some(roll.width), roll.length, roll.cut_every]
+                .into_iter()
+                .flatten()
+                .any(|length| length <= 0.0)
+            {
+                return Err("roll lengths must be greater than 0".to_owned());
+            }
+            layout::Size::new(roll.width, roll.length.unwrap_or(roll.width))
+        }
+        None if options.roll_length.is_some() || options.cut_every.is_some() => {
+            return Err("--roll-length and --cut-every need --roll".to_owned());
+        }
+        None if options.landscape => options.page.size().turned(),
+        None => options.page.size(),
+    };
+    if options.overlap < 0.0 || options.overlap >= page.width.min(page.height) / 2.0 {
+        return Err("overlap must be at least 0 and less than half the page".to_owned());
+    }
+    Ok(Some(options))
+}
+
+/// Parses a length such as `120cm`, `1.2 m` or `47 1/4in` into inches.
+fn parse_length(text: &str) -> Result<f32, String> {
+    match layout::parse_length(text, Units::Inches) {
+        Ok(LengthInput::Inches(inches)) => Ok(inches),
+        Ok(LengthInput::Percent(_)) => Err(format!("'{text}': percentages are not supported")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// Like [`parse_length`], but no overlap at all is allowed too.
+fn parse_overlap(text: &str) -> Result<f32, String> {
+    match layout::parse_signed_length(text, Units::Inches) {
+        Ok(LengthInput::Inches(inches)) if inches >= 0.0 => Ok(inches),
+        Ok(LengthInput::Inches(_)) => Err(format!("'{text}': the overlap cannot be negative")),
+        _ => parse_length(text),
+    }
+}
+
+/// Parses `a0`, `a1` or a custom size such as `36x48in` or `90cm x 2m`.
+fn parse_sheet(text: &str) -> Result<Sheet, String> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "a0" => return Ok(Sheet::A0),
+        "a1" => return Ok(Sheet::A1),
+        _ => {}
+    }
+    if !text.contains(['x', 'X']) {
+        return Err(format!(
+            "'{text}' is not a sheet; use a0, a1 or WIDTHxHEIGHT"
+        ));
+    }
+    let (width, height) = parse_dimensions(text)?;
+    Ok(Sheet::Custom { width, height })
+}
+
+/// Parses a size such as `36x48in` or `2m x 1.5m` into inches.
+fn parse_dimensions(text: &str) -> Result<(f32, f32), String> {
+    let (width, height) = text
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("'{text}' is not a size; use WIDTHxHEIGHT"))?;
+    // A unit written once at the end applies to both sides, as in `36x48in`.
+    let width = match width.trim().parse::<f32>() {
+        Ok(number) => {
+            let unit = height
+                .trim()
+                .trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '.' | '/' | ' '));
+            parse_length(&format!("{number}{unit}"))?
+        }
+        Err(_) => parse_length(width)?,
+    };
+    let height = parse_length(height)?;
+    if width <= 0.0 || height <= 0.0 {
+        return Err(format!("'{text}' is not a size"));
+    }
+    Ok((width, height))
+}
+
+fn parse_count(text: &str) -> Result<u32, String> {
+    text.trim()
+        .parse()
+        .ok()
+        .filter(|count| *count > 0)
+        .ok_or_else(|| format!("'{text}' is not a number of pages"))
+}
+
+fn parse_fit(text: &str) -> Result<Fit, String> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "stretch" => Ok(Fit::Stretch),
+        "contain" => Ok(Fit::Contain),
+        "cover" => Ok(Fit::Cover),
+        _ => Err(format!(
+            "unknown fit '{text}'; use stretch, contain or cover"
+        )),
+    }
+}
+
+fn parse_anchor(text: &str) -> Result<Anchor, String> {
+    let name = text.trim().to_ascii_lowercase().replace(['-', '_'], " ");
+    if name == "center" {
+        return Ok(Anchor::Center);
+    }
+    Anchor::ALL
+        .into_iter()
+        .find(|anchor| anchor.name() == name)
+        .ok_or_else(|| format!("unknown alignment '{text}'"))
+}
+
+fn parse_overlays(text: &str) -> Result<Overlays, String> {
+    let mut overlays = Overlays::default();
+    for name in text.split(',').map(str::trim) {
+        let overlay = match name.to_ascii_lowercase().as_str() {
+            "all" => {
+                Overlay::ALL
+                    .into_iter()
+                    .for_each(|overlay| overlays.set(overlay, true));
+                continue;
+            }
+            "crop" => Overlay::CropMarks,
+            "labels" => Overlay::Labels,
+            "grid" => Overlay::Grid,
+            "ruler" => Overlay::Ruler,
+            "outline" => Overlay::Outline,
+            _ => return Err(format!("unknown overlay '{name}'")),
+        };
+        overlays.set(overlay, true);
+    }
+    Ok(overlays)
+}
+
+fn parse_dpi(text: &str) -> Result<f32, String> {
+    text.trim()
+        .trim_end_matches("dpi")
+        .trim()
+        .parse()
+        .ok()
+        .filter(|dpi: &f32| *dpi > 0.0)
+        .ok_or_else(|| format!("'{text}' is not a resolution"))
+}
+
+fn parse_page(text: &str) -> Result<Page, String> {
+    match text.to_ascii_lowercase().as_str() {
+        "letter" => Ok(Page::Letter),
+        "a4" => Ok(Page::A4),
+        "legal" => Ok(Page::Legal),
+        "tabloid" => Ok(Page::Tabloid),
+        _ => Err(format!("unknown page size '{text}'")),
+    }
+}
+
+/// Resolves files, directories and glob patterns into a list of image files.
+fn expand_inputs(patterns: &[OsString]) -> Result<Vec<PathBuf>, String> {
+    let mut inputs = Vec::new();
+    for pattern in patterns {
+        let path = Path::new(pattern);
+        let pattern = pattern.to_string_lossy();
+        if path.is_dir() {
+            let mut images: Vec<PathBuf> = std::fs::read_dir(path)
+                .map_err(|err| format!("cannot read {pattern}: {err}"))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| is_image(path))
+                .collect();
+            images.sort();
+            inputs.extend(images);
+        } else if path.is_file() {
+            inputs.push(path.to_owned());
+        } else {
+            let matches =
+                glob::glob(&pattern).map_err(|err| format!("bad pattern {pattern}: {err}"))?;
+            let before = inputs.len();
+            inputs.extend(matches.filter_map(Result::ok).filter(|path| path.is_file()));
+            if inputs.len() == before {
+                return Err(format!("no images match '{pattern}'"));
+            }
+        }
+    }
+    Ok(inputs)
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            matches!(
+                ext.to_ascii_lowercase().as_str(),
+                "png" | "jpg" | "jpeg" | "svg"
+            )
+        })
+}
+
+fn output_path(input: &Path, output: Option<&Path>, batch: bool) -> PathBuf {
+    match output {
+        Some(output) if batch => output.join(input.with_extension("pdf").file_name().unwrap()),
+        Some(output) => output.to_owned(),
+        None => input.with_extension("pdf"),
+    }
+}
+
+/// The PDF each input is written to, or an error naming two inputs that would
+/// overwrite each other, e.g. `a/x.png` and `b/x.png` tiled into one directory.
+fn output_paths(inputs: &[PathBuf], output: Option<&Path>) -> Result<Vec<PathBuf>, String> {
+    let batch = inputs.len() > 1;
+    let mut outputs: Vec<PathBuf> = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let path = output_path(input, output, batch);
+        if let Some(other) = outputs.iter().position(|known| *known == path) {
+            return Err(format!(
+                "{} and {} would both be written to {}; tile them separately",
+                inputs[other].display(),
+                input.display(),
+                path.display()
+            ));
+        }
+        outputs.push(path);
+    }
+    Ok(outputs)
+}
+
+/// Tiles one image and returns each PDF written with its page columns and rows.
+fn tile(
+    input: &Path,
+    output: &Path,
+    options: &Options,
+) -> Result<Vec<(PathBuf, u32, u32)>, String> {
+    let data = std::fs::read(input).map_err(|err| err.to_string())?;
+    let source = SourceImage::decode(data).map_err(|err| err.to_string())?;
+    let aspect = source.image.height() as f32 / source.image.width() as f32;
+    let mut landscape = options.landscape;
+    let (width, height) = match (options.width, options.height) {
+        _ if options.size_constraint.is_some() => {
+            let solved = options
+                .size_constraint
+                .and_then(|constraint| {
+                    constraint.solve(aspect, options.page.size(), options.overlap)
+                })
+                .ok_or("no poster size meets the requirement")?;
+            landscape = solved.landscape;
+            (solved.poster.width, solved.poster.height)
+        }
+        (Some(width), Some(height)) => (width, height),
+        (Some(width), None) => (width, width * aspect),
+        (None, Some(height)) => (height / aspect, height),
+        (None, None) => {
+            let (dpi_x, dpi_y) = source
+                .dpi
+                .ok_or("no embedded resolution; pass --width or --height")?;
+            (
+                source.image.width() as f32 / dpi_x,
+                source.image.height() as f32 / dpi_y,
+            )
+        }
+    };
+
+    let job = TileJob::new(width, height)
+        .with_page(options.page)
+        .with_landscape(landscape)
+        .with_roll(options.roll)
+        .with_overlap(options.overlap)
+        .with_fit(options.fit, options.anchor)
+        .with_resample(options.resample)
+        .with_crop_tiles(options.crop_tiles)
+        .with_print_reminder(options.print_reminder)
+        .with_overlays(options.overlays)
+        .with_sheet(options.sheet)
+        .with_title(options.title.clone().or_else(|| {
+            input
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+        }))
+        .with_author(options.author.clone());
+    let pdf = generate_pdf(&job, &source.bytes).map_err(|err| err.to_string())?;
+    std::fs::write(output, pdf)
+        .map_err(|err| format!("cannot write {}: {err}", output.display()))?;
+    let layout = job.layout();
+    let mut written = vec![(output.to_owned(), layout.columns, layout.rows)];
+
+    let sheet_pdf = generate_sheet_pdf(&job, &source.bytes).map_err(|err| err.to_string())?;
+    if let (Some(pdf), Some(sheet), Some(layout)) = (sheet_pdf, job.sheet(), job.sheet_layout()) {
+        let output = sheet_path(output, sheet);
+        std::fs::write(&output, pdf)
+            .map_err(|err| format!("cannot write {}: {err}", output.display()))?;
+        written.push((output, layout.columns, layout.rows));
+    }
+    Ok(written)
+}
+
+/// Where the copy-shop PDF goes: next to `output`, e.g. `poster-a0.pdf`.
+fn sheet_path(output: &Path, sheet: Sheet) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    output.with_file_name(format!("{stem}-{}.pdf", sheet.name().to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn batch_outputs_are_named_after_the_inputs() {
+        let inputs = paths(&["a/x.png", "a/y.jpg", "b/z.svg"]);
+        assert_eq!(
+            output_paths(&inputs, Some(Path::new("out"))).unwrap(),
+            paths(&["out/x.pdf", "out/y.pdf", "out/z.pdf"])
+        );
+        assert_eq!(
+            output_paths(&inputs, None).unwrap(),
+            paths(&["a/x.pdf", "a/y.pdf", "b/z.pdf"])
+        );
+        assert_eq!(
+            output_paths(&paths(&["a/x.png"]), Some(Path::new("poster.pdf"))).unwrap(),
+            paths(&["poster.pdf"])
+        );
+    }
+
+    #[test]
+    fn colliding_outputs_are_an_error() {
+        let inputs = paths(&["a/x.png", "b/x.png"]);
+        let err = output_paths(&inputs, Some(Path::new("out"))).unwrap_err();
+        assert!(err.contains("a/x.png") && err.contains("b/x.png"), "{err}");
+        // Side by side they do not collide, but another extension does.
+        assert!(output_paths(&inputs, None).is_ok());
+        assert!(output_paths(&paths(&["a/x.png", "a/x.svg"]), None).is_err());
+    }
+
+    #[test]
+    fn only_commands_and_flags_select_the_command_line() {
+        assert!(is_command("tile"));
+        assert!(is_command("--help"));
+        assert!(is_command("-w"));
+        assert!(!is_command("poster.png"));
+        assert!(!is_command("/home/me/tile.etracer"));
+    }
+}
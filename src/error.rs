@@ -0,0 +1,183 @@
+use krilla::error::KrillaError;
+
+#[derive(Debug)]
+pub struct ParseImageError;
+
+impl std::error::Error for ParseImageError {}
+
+impl std::fmt::Display for ParseImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to parse image. Must be [png, jpeg, svg].")
+    }
+}
+
+/// A length the user typed could not be understood.
+#[derive(Debug, PartialEq)]
+pub enum ParseLengthError {
+    /// The text does not start with a number.
+    Invalid(String),
+    /// The number is followed by something other than a known unit.
+    UnknownUnit(String),
+    /// The length is zero or negative.
+    NotPositive(String),
+    /// A percentage was given where the image's native size is unknown,
+    /// because its file does not record a resolution.
+    NoNativeSize(String),
+}
+
+impl std::error::Error for ParseLengthError {}
+
+impl std::fmt::Display for ParseLengthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseLengthError::Invalid(text) => write!(f, "'{text}' is not a length"),
+            ParseLengthError::UnknownUnit(unit) => write!(f, "unknown unit '{unit}'"),
+            ParseLengthError::NotPositive(text) => write!(f, "'{text}' is not a positive length"),
+            ParseLengthError::NoNativeSize(text) => write!(
+                f,
+                "'{text}' needs the image's native size, but its file records no resolution"
+            ),
+        }
+    }
+}
+
+/// Everything that can go wrong between loading an image and writing the PDF.
+#[derive(Debug)]
+pub enum Error {
+    /// The loaded file could not be decoded as an image.
+    Decode(image::ImageError),
+    /// The image could not be embedded into the PDF.
+    ParseImage(ParseImageError),
+    /// krilla refused to write the document.
+    Pdf(KrillaError),
+    /// krilla's output could not be amended with the entries it cannot write itself.
+    PdfUpdate(String),
+    /// A length typed into a field could not be used.
+    ParseLength(ParseLengthError),
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// The requested poster size is zero or negative.
+    InvalidSize { width: f32, height: f32 },
+    /// Neighbouring pages would share more than half a page, or a negative strip.
+    InvalidOverlap { overlap: f32, max: f32 },
+    /// Resampling would produce an image with more than [`crate::resample::MAX_PIXELS`] pixels.
+    ImageTooLarge { width: u32, height: u32 },
+    /// A project file is damaged or not a project file at all.
+    InvalidProject(String),
+    /// A text banner could not be set, usually because of its font.
+    InvalidBanner(String),
+    /// A caption's font file, by name, is not a font krilla can read.
+    InvalidFont(String),
+    /// A project file was written by a newer version of etracer.
+    UnsupportedProjectVersion(u32),
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Decode(err) => Some(err),
+            Error::ParseImage(err) => Some(err),
+            Error::ParseLength(err) => Some(err),
+            Error::Io(err) => Some(err),
+            Error::Pdf(_)
+            | Error::PdfUpdate(_)
+            | Error::InvalidSize { .. }
+            | Error::InvalidOverlap { .. }
+            | Error::ImageTooLarge { .. }
+            | Error::InvalidProject(_)
+            | Error::InvalidBanner(_)
+            | Error::InvalidFont(_)
+            | Error::UnsupportedProjectVersion(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Decode(err) => write!(f, "Failed to decode image: {err}"),
+            Error::ParseImage(err) => write!(f, "{err}"),
+            Error::Pdf(err) => match err {
+                KrillaError::SubsetError(_, reason) => {
+                    write!(
+                        f,
+                        "Failed to write PDF: a font could not be embedded ({reason})."
+                    )
+                }
+                KrillaError::UserError(reason) => write!(f, "Failed to write PDF: {reason}"),
+                KrillaError::ValidationError(errors) => write!(
+                    f,
+                    "Failed to write PDF: it breaks {} rules of the PDF standard it targets.",
+                    errors.len()
+                ),
+            },
+            Error::PdfUpdate(reason) => write!(f, "Failed to finish PDF: {reason}"),
+            Error::ParseLength(err) => write!(f, "Invalid length: {err}"),
+            Error::Io(err) => write!(f, "File access failed: {err}"),
+            Error::InvalidSize { width, height } => {
+                write!(f, "Invalid poster size {width} x {height}.")
+            }
+            Error::InvalidOverlap { overlap, max } => write!(
+                f,
+                "Invalid overlap {overlap} in; it must be between 0 and half the page, {max} in."
+            ),
+            Error::ImageTooLarge { width, height } => write!(
+                f,
+                "Resampling to {width} x {height} pixels would be too large; lower the target dpi."
+            ),
+            Error::InvalidProject(reason) => write!(f, "Failed to open project: {reason}"),
+            Error::InvalidBanner(reason) => write!(f, "Failed to make the banner: {reason}"),
+            Error::InvalidFont(name) => write!(f, "Failed to read the font {name}."),
+            Error::UnsupportedProjectVersion(version) => write!(
+                f,
+                "Project file version {version} is newer than this etracer supports."
+            ),
+        }
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Error::Decode(err)
+    }
+}
+
+impl From<ParseImageError> for Error {
+    fn from(err: ParseImageError) -> Self {
+        Error::ParseImage(err)
+    }
+}
+
+impl From<ParseLengthError> for Error {
+    fn from(err: ParseLengthError) -> Self {
+        Error::ParseLength(err)
+    }
+}
+
+impl From<KrillaError> for Error {
+    fn from(err: KrillaError) -> Self {
+        Error::Pdf(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdf_errors_read_as_sentences() {
+        let err = Error::Pdf(KrillaError::UserError("page 3 does not exist".to_owned()));
+        assert_eq!(
+            err.to_string(),
+            "Failed to write PDF: page 3 does not exist"
+        );
+        let err = Error::Pdf(KrillaError::ValidationError(Vec::new()));
+        assert!(!err.to_string().contains("ValidationError"));
+    }
+}
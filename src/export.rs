@@ -0,0 +1,723 @@
+//! PDF generation for tiled posters.
+//!
+//! ```no_run
+//! use etracer::export::{generate_pdf, TileJob};
+//! use etracer::layout::Page;
+//!
+//! let image = std::fs::read("poster.png")?;
+//! let job = TileJob::new(47.0, 33.0).with_page(Page::A4).with_overlap(0.4);
+//! println!("{} pages", job.layout().page_count());
+//! std::fs::write("poster.pdf", generate_pdf(&job, &image)?)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use image::{DynamicImage, ImageFormat};
+use std::num::NonZeroU32;
+
+use krilla::color::rgb;
+use krilla::destination::XyzDestination;
+use krilla::font::Font;
+use krilla::geom::{Point, Transform};
+use krilla::metadata::Metadata;
+use krilla::outline::{Outline, OutlineNode};
+use krilla::page::PageLabel;
+use krilla::path::{Fill, FillRule, PathBuilder};
+use krilla::surface::{Surface, TextDirection};
+use krilla::{PageSettings, SvgSettings};
+use pdf_writer::types::NumberingStyle;
+
+use crate::caption::{Caption, CaptionFont};
+use crate::error::{Error, ParseImageError};
+use crate::layout::{
+    max_overlap, row_label, Anchor, Fit, Page, Rect, Roll, Sheet, Size, Tile, TileLayout,
+};
+use crate::overlay::{self, Overlays};
+use crate::pdf_update::PdfUpdate;
+use crate::resample::{self, Resample, ResampleFilter, MAX_PIXELS};
+use crate::source;
+
+/// PDF user space units per inch.
+pub const POINTS_PER_INCH: f32 = 72.0;
+
+/// Everything needed to tile one poster: its printed size, the paper and how
+/// much neighbouring pages overlap. Lengths are in inches.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TileJob {
+    size: Size,
+    page: Page,
+    landscape: bool,
+    roll: Option<Roll>,
+    overlap: f32,
+    fit: Fit,
+    anchor: Anchor,
+    resample: Option<Resample>,
+    crop_tiles: bool,
+    print_reminder: bool,
+    overlays: Overlays,
+    sheet: Option<Sheet>,
+    captions: Vec<Caption>,
+    title: Option<String>,
+    author: Option<String>,
+}
+
+impl TileJob {
+    /// A job printing the poster `width` x `height` inches on Letter paper without overlap.
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            size: Size::new(width, height),
+            page: Page::Letter,
+            landscape: false,
+            roll: None,
+            overlap: 0.0,
+            fit: Fit::Stretch,
+            anchor: Anchor::Center,
+            resample: None,
+            crop_tiles: false,
+            print_reminder: false,
+            overlays: Overlays::default(),
+            sheet: None,
+            captions: Vec::new(),
+            title: None,
+            author: None,
+        }
+    }
+
+    pub fn with_page(mut self, page: Page) -> Self {
+        self.page = page;
+        self
+    }
+
+    /// Turns the pages to landscape.
+    pub fn with_landscape(mut self, landscape: bool) -> Self {
+        self.landscape = landscape;
+        self
+    }
+
+    /// Prints on roll paper instead of the page size, in strips along the
+    /// poster's longer side.
+    pub fn with_roll(mut self, roll: Option<Roll>) -> Self {
+        self.roll = roll;
+        self
+    }
+
+    /// Sets the width of the strip neighbouring pages share, to leave room for glue.
+    pub fn with_overlap(mut self, overlap: f32) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Sets how the image fills a poster of different proportions, and where
+    /// it is aligned when it does not fill it exactly.
+    pub fn with_fit(mut self, fit: Fit, anchor: Anchor) -> Self {
+        self.fit = fit;
+        self.anchor = anchor;
+        self
+    }
+
+    /// Resamples the image to a set resolution before embedding it, instead of
+    /// embedding the file as it is.
+    pub fn with_resample(mut self, resample: Option<Resample>) -> Self {
+        self.resample = resample;
+        self
+    }
+
+    /// Embeds only the part of the image each page shows, as its own image,
+    /// instead of the whole image clipped by every page. Pages then stay small
+    /// and can be printed on their own.
+    pub fn with_crop_tiles(mut self, crop_tiles: bool) -> Self {
+        self.crop_tiles = crop_tiles;
+        self
+    }
+
+    /// Prints a small reminder on every page to print at actual size.
+    pub fn with_print_reminder(mut self, print_reminder: bool) -> Self {
+        self.print_reminder = print_reminder;
+        self
+    }
+
+    /// Draws printing aids over the pages, each kind in a layer viewers can hide.
+    pub fn with_overlays(mut self, overlays: Overlays) -> Self {
+        self.overlays = overlays;
+        self
+    }
+
+    /// Also lays the poster out on large-format paper, for printing it in one
+    /// piece at a copy shop. See [`generate_sheet_pdf`].
+    pub fn with_sheet(mut self, sheet: Option<Sheet>) -> Self {
+        self.sheet = sheet;
+        self
+    }
+
+    /// Places text on the poster, drawn over the image.
+    pub fn with_captions(mut self, captions: Vec<Caption>) -> Self {
+        self.captions = captions;
+        self
+    }
+
+    /// Sets the document title, usually the source image's file name.
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    pub fn page(&self) -> Page {
+        self.page
+    }
+
+    pub fn landscape(&self) -> bool {
+        self.landscape
+    }
+
+    pub fn roll(&self) -> Option<Roll> {
+        self.roll
+    }
+
+    pub fn overlap(&self) -> f32 {
+        self.overlap
+    }
+
+    pub fn fit(&self) -> Fit {
+        self.fit
+    }
+
+    pub fn anchor(&self) -> Anchor {
+        self.anchor
+    }
+
+    pub fn resample(&self) -> Option<Resample> {
+        self.resample
+    }
+
+    pub fn crop_tiles(&self) -> bool {
+        self.crop_tiles
+    }
+
+    pub fn print_reminder(&self) -> bool {
+        self.print_reminder
+    }
+
+    pub fn overlays(&self) -> Overlays {
+        self.overlays
+    }
+
+    pub fn sheet(&self) -> Option<Sheet> {
+        self.sheet
+    }
+
+    pub fn captions(&self) -> &[Caption] {
+        &self.captions
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Document properties describing the poster and how it is split.
+    fn metadata(&self, layout: &TileLayout, paper: &str) -> Metadata {
+        let subject = format!(
+            "{:.2} x {:.2} in poster on {} x {} {paper} pages",
+            self.size.width, self.size.height, layout.columns, layout.rows
+        );
+        let metadata = Metadata::new()
+            .title(self.title.clone().unwrap_or_else(|| "Poster".to_owned()))
+            .subject(subject)
+            .creator(format!("etracer {}", env!("CARGO_PKG_VERSION")));
+        match &self.author {
+            Some(author) => metadata.authors(vec![author.clone()]),
+            None => metadata,
+        }
+    }
+
+    /// Splits the poster into pages.
+    pub fn layout(&self) -> TileLayout {
+        match self.roll {
+            Some(roll) => TileLayout::on_roll(self.size, roll, self.overlap),
+            None => TileLayout::new(self.size, self.page_size(), self.overlap),
+        }
+    }
+
+    /// Where an image `image_size` in any unit goes on the poster, as set by [`TileJob::with_fit`].
+    pub fn image_area(&self, image_size: Size) -> Rect {
+        self.fit.place(self.size, image_size, self.anchor)
+    }
+
+    /// The size an image of `width` x `height` pixels prints at once fitted to the poster.
+    pub fn image_print_size(&self, width: u32, height: u32) -> Size {
+        let area = self.image_area(Size::new(width as f32, height as f32));
+        Size::new(area.width, area.height)
+    }
+
+    /// The size of the cut pages, turned as the job asks.
+    fn page_size(&self) -> Size {
+        if self.landscape {
+            self.page.size().turned()
+        } else {
+            self.page.size()
+        }
+    }
+
+    /// The paper the pages are printed on, for the document properties.
+    fn paper_name(&self) -> String {
+        match self.roll {
+            Some(roll) => format!("{:.2} in roll", roll.width),
+            None if self.landscape => format!("{:?} landscape", self.page),
+            None => format!("{:?}", self.page),
+        }
+    }
+
+    /// The poster at the same scale on the job's large-format sheet, if it has one.
+    pub fn sheet_layout(&self) -> Option<TileLayout> {
+        self.sheet
+            .map(|sheet| TileLayout::on_sheet(self.size, sheet.size()))
+    }
+}
+
+/// Generates the whole PDF in one go. `image_data` must be a PNG, JPEG or SVG file.
+pub fn generate_pdf(job: &TileJob, image_data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut export = PdfExport::new(job, image_data)?;
+    while export.write_next_page()? {}
+    export.finish()
+}
+
+/// Generates the copy-shop companion to [`generate_pdf`]: the same poster on
+/// the job's large-format sheet. `None` if the job has no sheet.
+pub fn generate_sheet_pdf(job: &TileJob, image_data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let Some(mut export) = PdfExport::for_sheet(job, image_data)? else {
+        return Ok(None);
+    };
+    while export.write_next_page()? {}
+    export.finish().map(Some)
+}
+
+/// Prepares a PNG or JPEG image as the job asks: resampled, and whole or
+/// cropped per page.
+fn embed_raster(job: &TileJob, image_data: &[u8]) -> Result<Embedded, Error> {
+    let resampled = resample_image(job, image_data)?;
+    Ok(match (resampled, job.crop_tiles()) {
+        (Some((image, format)), true) => Embedded::PerTile { image, format },
+        (Some((image, format)), false) => {
+            Embedded::Whole(parse_krilla_image(&source::encode(&image, format)?)?)
+        }
+        (None, true) => {
+            let format = image::guess_format(image_data)?;
+            let image = image::load_from_memory_with_format(image_data, format)?;
+            Embedded::PerTile { image, format }
+        }
+        (None, false) => Embedded::Whole(parse_krilla_image(image_data)?),
+    })
+}
+
+/// Applies the job's resampling to `image_data`, returning the resized image
+/// and its format, or `None` if it is embedded unchanged.
+fn resample_image(
+    job: &TileJob,
+    image_data: &[u8],
+) -> Result<Option<(DynamicImage, ImageFormat)>, Error> {
+    let Some(resample) = job.resample() else {
+        return Ok(None);
+    };
+    let format = image::guess_format(image_data)?;
+    let image = image::load_from_memory_with_format(image_data, format)?;
+    let printed = job.image_print_size(image.width(), image.height());
+    let Some((width, height)) = resample.target_pixels(image.width(), image.height(), printed)
+    else {
+        return Ok(None);
+    };
+    if width as u64 * height as u64 > MAX_PIXELS {
+        return Err(Error::ImageTooLarge { width, height });
+    }
+    let image = resample::resample(&image, width, height, resample.filter);
+    // JPEG artefacts would undo the sharp edges of line art.
+    let format = match resample.filter {
+        ResampleFilter::Lanczos => format,
+        ResampleFilter::LineArt => ImageFormat::Png,
+    };
+    Ok(Some((image, format)))
+}
+
+fn parse_krilla_image(data: &[u8]) -> Result<krilla::image::Image, ParseImageError> {
+    if let Some(png) = krilla::image::Image::from_png(data) {
+        return Ok(png);
+    }
+    if let Some(jpeg) = krilla::image::Image::from_jpeg(data) {
+        return Ok(jpeg);
+    }
+    Err(ParseImageError)
+}
+
+/// How the image ends up in the document.
+enum Embedded {
+    /// One image object, shared by every page and clipped by each.
+    Whole(krilla::image::Image),
+    /// Each page gets its own crop of these pixels.
+    PerTile {
+        image: DynamicImage,
+        format: ImageFormat,
+    },
+    /// An SVG drawn as vectors on every page. Resampling and cropping do not apply.
+    Vector(resvg::usvg::Tree),
+}
+
+impl Embedded {
+    /// The image's proportions, in pixels or SVG units.
+    fn size(&self) -> Size {
+        match self {
+            Embedded::Whole(image) => Size::new(image.size().width(), image.size().height()),
+            Embedded::PerTile { image, .. } => {
+                Size::new(image.width() as f32, image.height() as f32)
+            }
+            Embedded::Vector(tree) => Size::new(tree.size().width(), tree.size().height()),
+        }
+    }
+}
+
+/// Builds the tiled PDF one page at a time, so callers can report progress
+/// and cancel between pages.
+pub struct PdfExport {
+    doc: krilla::Document,
+    image: Embedded,
+    /// Font for the print reminder, if the job asks for one.
+    reminder_font: Option<Font>,
+    /// The job's captions, each with its font loaded.
+    captions: Vec<(Caption, Font)>,
+    overlays: Overlays,
+    layout: TileLayout,
+    pages_done: u32,
+}
+
+impl PdfExport {
+    /// Prepares the document. This is where resampling happens, so it may take a while.
+    pub fn new(job: &TileJob, image_data: &[u8]) -> Result<Self, Error> {
+        let layout = job.layout();
+        // The layout clamps the overlap; exporting anything but what was asked is an error.
+        if layout.overlap != job.overlap() {
+            return Err(Error::InvalidOverlap {
+                overlap: job.overlap(),
+                max: max_overlap(layout.page),
+            });
+        }
+        Self::with_layout(job, image_data, layout, &job.paper_name())
+    }
+
+    /// Like [`PdfExport::new`], but for the copy-shop sheet. `None` if the job has no sheet.
+    pub fn for_sheet(job: &TileJob, image_data: &[u8]) -> Result<Option<Self>, Error> {
+        match (job.sheet(), job.sheet_layout()) {
+            (Some(sheet), Some(layout)) => {
+                Self::with_layout(job, image_data, layout, sheet.name()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn with_layout(
+        job: &TileJob,
+        image_data: &[u8],
+        layout: TileLayout,
+        paper: &str,
+    ) -> Result<Self, Error> {
+        let size = job.size();
+        if krilla::geom::Size::from_wh(size.width, size.height).is_none() {
+            return Err(Error::InvalidSize {
+                width: size.width,
+                height: size.height,
+            });
+        }
+
+        let image = if source::is_svg(image_data) {
+            Embedded::Vector(source::parse_svg(image_data)?)
+        } else {
+            embed_raster(job, image_data)?
+        };
+        let layout = layout.with_image(job.image_area(image.size()));
+
+        let reminder_font = if job.print_reminder() {
+            Some(CaptionFont::Sans.load()?)
+        } else {
+            None
+        };
+
+        let captions = job
+            .captions()
+            .iter()
+            .map(|caption| Ok((caption.clone(), caption.font.load()?)))
+            .collect::<Result<_, Error>>()?;
+
+        let mut overlays = job.overlays();
+        // Cut marks along roll pages are drawn with the crop marks.
+        overlays.crop_marks |= layout.roll.is_some_and(|roll| roll.cut_every.is_some());
+
+        let mut doc = krilla::Document::new();
+        doc.set_metadata(job.metadata(&layout, paper));
+        doc.set_outline(row_outline(&layout));
+
+        Ok(Self {
+            doc,
+            image,
+            reminder_font,
+            captions,
+            overlays,
+            layout,
+            pages_done: 0,
+        })
+    }
+
+    pub fn layout(&self) -> &TileLayout {
+        &self.layout
+    }
+
+    pub fn total_pages(&self) -> u32 {
+        self.layout.page_count()
+    }
+
+    pub fn pages_done(&self) -> u32 {
+        self.pages_done
+    }
+
+    /// Writes the next page, row by row. Returns `false` once every page has been written.
+    pub fn write_next_page(&mut self) -> Result<bool, Error> {
+        if self.pages_done >= self.total_pages() {
+            return Ok(false);
+        }
+        let tile = self.layout.tile(
+            self.pages_done % self.layout.columns,
+            self.pages_done / self.layout.columns,
+        );
+        let placed = self.layout.image;
+
+        // The image to draw and the poster area it covers, in inches.
+        let (image, area) = match &self.image {
+            Embedded::Whole(image) => (Some(image.clone()), placed),
+            Embedded::PerTile { image, format } => match crop_to_tile(image, &tile.uv) {
+                Some((crop, pixels)) => {
+                    let bytes = source::encode(&crop, *format)?;
+                    let area = Rect::new(
+                        placed.x + pixels.x * placed.width,
+                        placed.y + pixels.y * placed.height,
+                        pixels.width * placed.width,
+                        pixels.height * placed.height,
+                    );
+                    (Some(parse_krilla_image(&bytes)?), area)
+                }
+                None => (None, tile.image),
+            },
+            Embedded::Vector(_) => (None, placed),
+        };
+
+        // Viewers list the page as e.g. "B3", matching its place in the grid.
+        let label = PageLabel::new(
+            Some(NumberingStyle::Arabic),
+            Some(row_label(tile.row)),
+            NonZeroU32::new(tile.column + 1).expect("columns count from 1"),
+        );
+        let mut page = self.doc.start_page_with(
+            PageSettings::new(
+                tile.page.width * POINTS_PER_INCH,
+                tile.page.height * POINTS_PER_INCH,
+            )
+            .with_page_label(label),
+        );
+        let mut surface = page.surface();
+        // A cropped image reaches past the poster, and whole pixels past the page's share.
+        let clip = content_clip(&tile);
+        if let Some(clip) = &clip {
+            surface.push_clip_path(clip, &FillRule::NonZero);
+        }
+        if let Some(image) = image {
+            surface.push_transform(&Transform::from_translate(
+                (area.x - tile.page.x) * POINTS_PER_INCH,
+                (area.y - tile.page.y) * POINTS_PER_INCH,
+            ));
+            let size = krilla::geom::Size::from_wh(
+                area.width * POINTS_PER_INCH,
+                area.height * POINTS_PER_INCH,
+            )
+            .expect("image area is never empty");
+            surface.draw_image(image, size);
+            surface.pop();
+        }
+        if let Embedded::Vector(tree) = &self.image {
+            // The drawing is scaled to its place on the poster and clipped by the page.
+            surface.push_transform(&Transform::from_translate(
+                (area.x - tile.page.x) * POINTS_PER_INCH,
+                (area.y - tile.page.y) * POINTS_PER_INCH,
+            ));
+            let size = krilla::geom::Size::from_wh(
+                area.width * POINTS_PER_INCH,
+                area.height * POINTS_PER_INCH,
+            )
+            .expect("image area is never empty");
+            surface
+                .draw_svg(tree, size, SvgSettings::default())
+                .ok_or(Error::ParseImage(ParseImageError))?;
+            surface.pop();
+        }
+        if clip.is_some() {
+            surface.pop();
+        }
+        if !self.captions.is_empty() {
+            draw_captions(&mut surface, &self.captions, &tile);
+        }
+        if let Some(font) = &self.reminder_font {
+            draw_print_reminder(
+                &mut surface,
+                font.clone(),
+                tile.page.height * POINTS_PER_INCH,
+            );
+        }
+        surface.finish();
+        page.finish();
+
+        self.pages_done += 1;
+        Ok(true)
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, Error> {
+        let mut pdf = PdfUpdate::new(self.doc.finish()?)?;
+        // Viewers default to shrinking pages to the printable area, which breaks
+        // the tiling. Posters are also printed one-sided, each page on paper of
+        // its own size.
+        pdf.set_catalog_entry(
+            "/ViewerPreferences << /PrintScaling /None /Duplex /Simplex /PickTrayByPDFSize true >>",
+        );
+        // Open with the row bookmarks showing.
+        pdf.set_catalog_entry("/PageMode /UseOutlines");
+        overlay::add_layers(&mut pdf, &self.layout, self.overlays)?;
+        Ok(pdf.finish())
+    }
+}
+
+/// A bookmark for every row of pages, pointing at the row's first page.
+fn row_outline(layout: &TileLayout) -> Outline {
+    let mut outline = Outline::new();
+    for row in 0..layout.rows {
+        let first = layout.tile(0, row).label();
+        let last = layout.tile(layout.columns - 1, row).label();
+        let title = if layout.columns > 1 {
+            format!("Row {} ({first}-{last})", row_label(row))
+        } else {
+            format!("Row {} ({first})", row_label(row))
+        };
+        let page_index = (row * layout.columns) as usize;
+        outline.push_child(OutlineNode::new(
+            title,
+            XyzDestination::new(page_index, Point::from_xy(0.0, 0.0)),
+        ));
+    }
+    outline
+}
+
+/// The part of `tile`'s page the poster covers, in page coordinates.
+/// `None` if the page lies entirely in the margin.
+fn content_clip(tile: &Tile) -> Option<krilla::path::Path> {
+    krilla::geom::Rect::from_xywh(
+        (tile.content.x - tile.page.x) * POINTS_PER_INCH,
+        (tile.content.y - tile.page.y) * POINTS_PER_INCH,
+        tile.content.width * POINTS_PER_INCH,
+        tile.content.height * POINTS_PER_INCH,
+    )
+    .map(PathBuilder::from_rect)
+}
+
+/// Draws the captions as they fall on `tile`'s page, cut off at the poster's edge.
+fn draw_captions(surface: &mut Surface<'_>, captions: &[(Caption, Font)], tile: &Tile) {
+    let Some(clip) = content_clip(tile) else {
+        return;
+    };
+    surface.push_clip_path(&clip, &FillRule::NonZero);
+    for (caption, font) in captions {
+        let [r, g, b] = caption.color;
+        for (line, baseline) in caption.lines() {
+            surface.fill_text(
+                Point::from_xy(
+                    (caption.x - tile.page.x) * POINTS_PER_INCH,
+                    (baseline - tile.page.y) * POINTS_PER_INCH,
+                ),
+                Fill {
+                    paint: rgb::Color::new(r, g, b).into(),
+                    ..Fill::default()
+                },
+                font.clone(),
+                caption.size.points(),
+                &[],
+                line,
+                false,
+                TextDirection::Auto,
+            );
+        }
+    }
+    surface.pop();
+}
+
+/// Text of the optional print reminder.
+const PRINT_REMINDER: &str = "Print at 100% (actual size), without fit to page";
+
+/// Draws [`PRINT_REMINDER`] in the bottom left corner of a page.
+fn draw_print_reminder(surface: &mut Surface<'_>, font: Font, page_height: f32) {
+    let font_size = 7.0;
+    let inset = 0.3 * POINTS_PER_INCH;
+    // Rough width of the text, which is plenty for a background box.
+    let width = PRINT_REMINDER.len() as f32 * font_size * 0.5;
+    let background = krilla::geom::Rect::from_xywh(
+        inset - 2.0,
+        page_height - inset - font_size - 2.0,
+        width + 4.0,
+        font_size + 5.0,
+    )
+    .map(PathBuilder::from_rect);
+    if let Some(background) = background {
+        surface.fill_path(
+            &background,
+            Fill {
+                paint: rgb::Color::white().into(),
+                ..Fill::default()
+            },
+        );
+    }
+    surface.fill_text(
+        Point::from_xy(inset, page_height - inset),
+        Fill {
+            paint: rgb::Color::gray(96).into(),
+            ..Fill::default()
+        },
+        font,
+        font_size,
+        &[],
+        PRINT_REMINDER,
+        false,
+        TextDirection::Auto,
+    );
+}
+
+/// The pixels of `image` covering `uv`, rounded outwards to whole pixels, and
+/// the area they cover in normalised image coordinates. `None` if `uv` is empty.
+fn crop_to_tile(image: &DynamicImage, uv: &Rect) -> Option<(DynamicImage, Rect)> {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let x0 = (uv.x * width).floor().max(0.0);
+    let y0 = (uv.y * height).floor().max(0.0);
+    let x1 = (uv.right() * width).ceil().min(width);
+    let y1 = (uv.bottom() * height).ceil().min(height);
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+    let crop = image.crop_imm(x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32);
+    let area = Rect::new(
+        x0 / width,
+        y0 / height,
+        (x1 - x0) / width,
+        (y1 - y0) / height,
+    );
+    Some((crop, area))
+}
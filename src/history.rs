@@ -0,0 +1,439 @@
+//! Undo/redo history of layout edits.
+
+use crate::layout::SizeConstraint;
+use crate::overlay::Overlay;
+use crate::project::LayoutSettings;
+
+/// The most edits kept; older ones are forgotten first.
+const MAX_ENTRIES: usize = 100;
+
+/// One state of the layout, and what changed to get there.
+pub struct HistoryEntry {
+    pub label: String,
+    pub settings: LayoutSettings,
+}
+
+/// Snapshots of [`LayoutSettings`], oldest first.
+///
+/// Undoing moves back through the list without dropping anything, so the undone
+/// edits can be redone until a new edit replaces them.
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    current: usize,
+}
+
+impl History {
+    pub fn new(settings: LayoutSettings) -> Self {
+        Self {
+            entries: vec![HistoryEntry {
+                label: "Initial layout".to_owned(),
+                settings,
+            }],
+            current: 0,
+        }
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Index of the entry matching the current layout.
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Adds `settings` as a new edit, unless they match the current entry.
+    pub fn record(&mut self, settings: &LayoutSettings) {
+        let previous = &self.entries[self.current].settings;
+        if previous == settings {
+            return;
+        }
+        let label = describe_change(previous, settings);
+        self.record_as(label, settings);
+    }
+
+    /// Like [`History::record`], but with a label chosen by the caller.
+    pub fn record_as(&mut self, label: impl Into<String>, settings: &LayoutSettings) {
+        self.entries.truncate(self.current + 1);
+        self.entries.push(HistoryEntry {
+            label: label.into(),
+            settings: settings.clone(),
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.current = self.entries.len() - 1;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current + 1 < self.entries.len()
+    }
+
+    /// Steps back one edit and returns the layout to restore.
+    pub fn undo(&mut self) -> Option<&LayoutSettings> {
+        self.can_undo().then(|| self.jump_to(self.current - 1))
+    }
+
+    /// Steps forward one undone edit and returns the layout to restore.
+    pub fn redo(&mut self) -> Option<&LayoutSettings> {
+        self.can_redo().then(|| self.jump_to(self.current + 1))
+    }
+
+    /// Moves to the entry at `index` and returns its layout.
+    pub fn jump_to(&mut self, index: usize) -> &LayoutSettings {
+        self.current = index.min(self.entries.len() - 1);
+        &self.entries[self.current].settings
+    }
+}
+
+/// A short description of the edit from `old` to `new`, for the history list.
+fn describe_change(old: &LayoutSettings, new: &LayoutSettings) -> String {
+    let units = new.units;
+    let length = |inches: f32| format!("{:.2} {}", units.from_inches(inches), units.suffix());
+    let mut changes = Vec::new();
+    if old.desired_width != new.desired_width {
+        changes.push(format!("Width {}", length(new.desired_width)));
+    }
+    if old.desired_height != new.desired_height {
+        changes.push(format!("Height {}", length(new.desired_height)));
+    }
+    if old.overlap != new.overlap {
+        changes.push(format!("Overlap {}", length(new.overlap)));
+    }
+    if old.page_size != new.page_size {
+        changes.push(format!("Page {:?}", new.page_size));
+    }
+    if old.landscape != new.landscape {
+        let orientation = if new.landscape {
+            "landscape"
+        } else {
+            "portrait"
+        };
+        changes.push(format!("Pages {orientation}"));
+    }
+    if old.size_constraint != new.size_constraint {
+        changes.push(match new.size_constraint {
+            Some(SizeConstraint::MaxPages(pages)) => format!("Size for at most {pages} pages"),
+            Some(SizeConstraint::PagesWide(columns)) => format!("Size {columns} pages wide"),
+            Some(SizeConstraint::PagesTall(rows)) => format!("Size {rows} pages tall"),
+            Some(SizeConstraint::Within { width, height }) => {
+                format!("Size to fit {} x {}", length(width), length(height))
+            }
+            None => "Size by hand".to_owned(),
+        });
+    }
+    if old.roll != new.roll {
+        match new.roll {
+            Some(roll) => {
+                let mut change = format!("Roll {}", length(roll.width));
+                if let Some(page_length) = roll.length {
+                    change += &format!(" x {}", length(page_length));
+                }
+                if let Some(every) = roll.cut_every {
+                    change += &format!(", cut every {}", length(every));
+                }
+                changes.push(change);
+            }
+            None => changes.push("No roll".to_owned()),
+        }
+    }
+    if old.units != new.units {
+        changes.push(format!("Units {:?}", new.units));
+    }
+    if old.resample != new.resample {
+        match new.resample {
+            Some(resample) => changes.push(format!("Resample to {:.0} dpi", resample.dpi)),
+            None => changes.push("No resampling".to_owned()),
+        }
+    }
+    if old.crop_tiles != new.crop_tiles {
+        let state = if new.crop_tiles { "on" } else { "off" };
+        changes.push(format!("Crop per page {state}"));
+    }
+    if old.print_reminder != new.print_reminder {
+        let state = if new.print_reminder { "on" } else { "off" };
+        changes.push(format!("Print reminder {state}"));
+    }
+    if old.sheet != new.sheet {
+        match new.sheet {
+            Some(sheet) => {
+                let size = sheet.size();
+                changes.push(format!(
+                    "Copy-shop sheet {} ({} x {})",
+                    sheet.name(),
+                    length(size.width),
+                    length(size.height)
+                ));
+            }
+            None => changes.push("No copy-shop sheet".to_owned()),
+        }
+    }
+    for overlay in Overlay::ALL {
+        let enabled = new.overlays.is_enabled(overlay);
+        if old.overlays.is_enabled(overlay) != enabled {
+            let state = if enabled { "on" } else { "off" };
+            changes.push(format!("{} {state}", overlay.name()));
+        }
+    }
+    if old.captions != new.captions {
+        changes.push(match new.captions.len().cmp(&old.captions.len()) {
+            std::cmp::Ordering::Greater => "Add caption".to_owned(),
+            std::cmp::Ordering::Less => "Remove caption".to_owned(),
+            std::cmp::Ordering::Equal => "Edit caption".to_owned(),
+        });
+    }
+    if old.fit != new.fit {
+        changes.push(new.fit.name().to_owned());
+    }
+    if old.anchor != new.anchor {
+        changes.push(format!("Align {}", new.anchor.name()));
+    }
+    if old.maintain_aspect_ratio != new.maintain_aspect_ratio {
+        let state = if new.maintain_aspect_ratio {
+            "on"
+        } else {
+            "off"
+        };
+        changes.push(format!("Aspect lock {state}"));
+    }
+    if changes.is_empty() {
+        "Edit layout".to_owned()
+    } else {
+        changes.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caption::Caption;
+    use crate::layout::{Anchor, Fit, Page, Roll, Sheet, Units};
+    use crate::resample::Resample;
+
+    fn with_width(width: f32) -> LayoutSettings {
+        LayoutSettings {
+            desired_width: width,
+            ..LayoutSettings::default()
+        }
+    }
+
+    fn labels(history: &History) -> Vec<&str> {
+        history
+            .entries()
+            .iter()
+            .map(|entry| entry.label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_settings_are_not_recorded() {
+        let mut history = History::new(LayoutSettings::default());
+        history.record(&LayoutSettings::default());
+        history.record(&with_width(20.0));
+        history.record(&with_width(20.0));
+        assert_eq!(history.entries().len(), 2);
+        assert_eq!(history.current(), 1);
+    }
+
+    #[test]
+    fn edits_between_records_become_one_entry() {
+        let mut history = History::new(LayoutSettings::default());
+        let mut settings = with_width(20.0);
+        settings.desired_height = 30.0;
+        history.record(&settings);
+        assert_eq!(
+            labels(&history),
+            ["Initial layout", "Width 20.00 in, Height 30.00 in"]
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_stop_at_the_ends() {
+        let mut history = History::new(LayoutSettings::default());
+        assert!(history.undo().is_none());
+        assert!(history.redo().is_none());
+        history.record(&with_width(20.0));
+        history.record(&with_width(30.0));
+
+        assert_eq!(history.undo().unwrap().desired_width, 20.0);
+        assert_eq!(history.undo().unwrap(), &LayoutSettings::default());
+        assert!(history.undo().is_none());
+        assert_eq!(history.current(), 0);
+
+        assert_eq!(history.redo().unwrap().desired_width, 20.0);
+        assert_eq!(history.redo().unwrap().desired_width, 30.0);
+        assert!(history.redo().is_none());
+        assert_eq!(history.current(), 2);
+    }
+
+    #[test]
+    fn jumps_stay_within_the_history() {
+        let mut history = History::new(LayoutSettings::default());
+        for width in [20.0, 30.0, 40.0] {
+            history.record(&with_width(width));
+        }
+        assert_eq!(history.jump_to(1).desired_width, 20.0);
+        assert!(history.can_undo() && history.can_redo());
+        assert_eq!(history.jump_to(99).desired_width, 40.0);
+        assert_eq!(history.current(), 3);
+        // Jumping back keeps the later entries for redo.
+        history.jump_to(0);
+        assert_eq!(history.entries().len(), 4);
+    }
+
+    #[test]
+    fn a_new_edit_discards_undone_ones() {
+        let mut history = History::new(LayoutSettings::default());
+        history.record(&with_width(20.0));
+        history.record(&with_width(30.0));
+        history.undo();
+        history.undo();
+        history.record(&with_width(50.0));
+        assert_eq!(labels(&history), ["Initial layout", "Width 50.00 in"]);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn oldest_entries_are_forgotten() {
+        let mut history = History::new(LayoutSettings::default());
+        for i in 1..=MAX_ENTRIES + 20 {
+            history.record(&with_width(i as f32));
+        }
+        assert_eq!(history.entries().len(), MAX_ENTRIES);
+        assert_eq!(history.current(), MAX_ENTRIES - 1);
+        assert_eq!(history.entries()[0].label, "Width 21.00 in");
+        while history.undo().is_some() {}
+        assert_eq!(history.jump_to(0).desired_width, 21.0);
+    }
+
+    #[test]
+    fn every_setting_has_its_own_label() {
+        let old = LayoutSettings::default();
+        // Naming every field makes a new one fail to compile here, and an
+        // unused binding fails clippy, until it has an edit below.
+        let LayoutSettings {
+            desired_width,
+            desired_height,
+            units,
+            page_size,
+            landscape,
+            roll,
+            maintain_aspect_ratio,
+            size_constraint,
+            fit,
+            anchor,
+            overlap,
+            resample,
+            crop_tiles,
+            print_reminder,
+            overlays,
+            sheet,
+            captions,
+        } = old.clone();
+        type Edit = Box<dyn Fn(&mut LayoutSettings)>;
+        let mut edits: Vec<(&str, Edit)> = vec![
+            (
+                "width",
+                Box::new(move |s| s.desired_width = desired_width + 1.0),
+            ),
+            (
+                "height",
+                Box::new(move |s| s.desired_height = desired_height + 1.0),
+            ),
+            (
+                "units",
+                Box::new(move |s| {
+                    s.units = match units {
+                        Units::Inches => Units::Centimeters,
+                        _ => Units::Inches,
+                    }
+                }),
+            ),
+            (
+                "page",
+                Box::new(move |s| {
+                    s.page_size = match page_size {
+                        Page::A4 => Page::Letter,
+                        _ => Page::A4,
+                    }
+                }),
+            ),
+            ("landscape", Box::new(move |s| s.landscape = !landscape)),
+            (
+                "roll",
+                Box::new(move |s| s.roll = roll.xor(Some(Roll::default()))),
+            ),
+            (
+                "aspect lock",
+                Box::new(move |s| s.maintain_aspect_ratio = !maintain_aspect_ratio),
+            ),
+            (
+                "size constraint",
+                Box::new(move |s| {
+                    s.size_constraint = size_constraint.xor(Some(SizeConstraint::MaxPages(4)))
+                }),
+            ),
+            (
+                "fit",
+                Box::new(move |s| {
+                    s.fit = match fit {
+                        Fit::Contain => Fit::Cover,
+                        _ => Fit::Contain,
+                    }
+                }),
+            ),
+            (
+                "anchor",
+                Box::new(move |s| {
+                    s.anchor = match anchor {
+                        Anchor::TopLeft => Anchor::Bottom,
+                        _ => Anchor::TopLeft,
+                    }
+                }),
+            ),
+            ("overlap", Box::new(move |s| s.overlap = overlap + 0.5)),
+            (
+                "resample",
+                Box::new(move |s| s.resample = resample.xor(Some(Resample::default()))),
+            ),
+            ("crop tiles", Box::new(move |s| s.crop_tiles = !crop_tiles)),
+            (
+                "print reminder",
+                Box::new(move |s| s.print_reminder = !print_reminder),
+            ),
+            (
+                "sheet",
+                Box::new(move |s| s.sheet = sheet.xor(Some(Sheet::A0))),
+            ),
+        ];
+        for overlay in Overlay::ALL {
+            edits.push((
+                overlay.name(),
+                Box::new(move |s| {
+                    let mut flipped = overlays;
+                    flipped.set(overlay, !overlays.is_enabled(overlay));
+                    s.overlays = flipped;
+                }),
+            ));
+        }
+        let mut more = captions.clone();
+        more.push(Caption::default());
+        edits.push(("captions", Box::new(move |s| s.captions = more.clone())));
+
+        for (field, edit) in edits {
+            let mut new = old.clone();
+            edit(&mut new);
+            assert_ne!(new, old, "{field} did not change");
+            let label = describe_change(&old, &new);
+            assert_ne!(label, "Edit layout", "{field} has no label");
+            assert!(
+                !label.contains(", "),
+                "{field} also changed others: {label}"
+            );
+        }
+    }
+}
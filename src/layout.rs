@@ -0,0 +1,864 @@
+//! Page-grid geometry for splitting a poster across printable pages.
+//!
+//! Lengths are in inches unless stated otherwise. The poster is centred on the
+//! grid of pages, so the blank margin is split evenly between opposite edges.
+
+use crate::error::ParseLengthError;
+
+/// Units lengths are shown in.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+pub enum Units {
+    Inches,
+    Centimeters,
+    Millimeters,
+    /// PostScript points, 72 to the inch.
+    Points,
+}
+
+impl Units {
+    pub const ALL: [Units; 4] = [
+        Units::Inches,
+        Units::Centimeters,
+        Units::Millimeters,
+        Units::Points,
+    ];
+
+    /// Abbreviation shown after values, e.g. `"cm"`.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Units::Inches => "in",
+            Units::Centimeters => "cm",
+            Units::Millimeters => "mm",
+            Units::Points => "pt",
+        }
+    }
+
+    /// How many of these units make an inch.
+    fn per_inch(self) -> f32 {
+        match self {
+            Units::Inches => 1.0,
+            Units::Centimeters => 2.54,
+            Units::Millimeters => 25.4,
+            Units::Points => 72.0,
+        }
+    }
+
+    /// Converts `value`, given in these units, to inches.
+    pub fn to_inches(self, value: f32) -> f32 {
+        value / self.per_inch()
+    }
+
+    /// Converts `inches` into these units.
+    pub fn from_inches(self, inches: f32) -> f32 {
+        inches * self.per_inch()
+    }
+}
+
+/// A length typed by the user, see [`parse_length`].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum LengthInput {
+    Inches(f32),
+    /// A percentage of the image's native size, e.g. `150%`.
+    Percent(f32),
+}
+
+/// Parses lengths such as `120cm`, `1.2 m`, `47 1/4 in` or `150%`.
+///
+/// Numbers may be decimals, fractions or a whole number followed by a fraction.
+/// A number without a unit is taken to be in `default_units`. Zero and
+/// negative lengths are rejected.
+pub fn parse_length(text: &str, default_units: Units) -> Result<LengthInput, ParseLengthError> {
+    let length = parse_signed_length(text, default_units)?;
+    match length {
+        LengthInput::Inches(value) | LengthInput::Percent(value) if value <= 0.0 => {
+            Err(ParseLengthError::NotPositive(text.trim().to_owned()))
+        }
+        _ => Ok(length),
+    }
+}
+
+/// Like [`parse_length`], but zero and negative lengths are allowed, for
+/// offsets and overlaps.
+pub fn parse_signed_length(
+    text: &str,
+    default_units: Units,
+) -> Result<LengthInput, ParseLengthError> {
+    let text = text.trim();
+    let number_end = text
+        .rfind(|c: char| c.is_ascii_digit() || c == '.')
+        .map_or(0, |i| i + 1);
+    let (number, unit) = text.split_at(number_end);
+    let number = parse_number(number).ok_or_else(|| ParseLengthError::Invalid(text.to_owned()))?;
+    let inches = match unit.trim().to_ascii_lowercase().as_str() {
+        "" => default_units.to_inches(number),
+        "%" => return Ok(LengthInput::Percent(number)),
+        "in" | "inch" | "inches" | "\"" => number,
+        "ft" | "'" => number * 12.0,
+        "pt" => Units::Points.to_inches(number),
+        "mm" => Units::Millimeters.to_inches(number),
+        "cm" => Units::Centimeters.to_inches(number),
+        "m" => Units::Centimeters.to_inches(number * 100.0),
+        _ => return Err(ParseLengthError::UnknownUnit(unit.trim().to_owned())),
+    };
+    Ok(LengthInput::Inches(inches))
+}
+
+/// Parses `1.5`, `3/4` or `47 1/4`.
+fn parse_number(text: &str) -> Option<f32> {
+    let fraction = |part: &str| -> Option<f32> {
+        match part.split_once('/') {
+            Some((numerator, denominator)) => {
+                let denominator: f32 = denominator.parse().ok()?;
+                (denominator != 0.0).then_some(numerator.parse::<f32>().ok()? / denominator)
+            }
+            None => part.parse().ok(),
+        }
+    };
+    let mut parts = text.split_whitespace();
+    let value = match (parts.next()?, parts.next(), parts.next()) {
+        (whole, Some(part), None) if part.contains('/') && !whole.contains('/') => {
+            whole.parse::<f32>().ok()? + fraction(part)?
+        }
+        (part, None, None) => fraction(part)?,
+        _ => return None,
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Paper sizes the poster can be printed on, in portrait orientation.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+pub enum Page {
+    Letter,
+    A4,
+    Legal,
+    Tabloid,
+}
+
+impl Page {
+    pub const ALL: [Page; 4] = [Page::Letter, Page::A4, Page::Legal, Page::Tabloid];
+
+    pub fn size(&self) -> Size {
+        match self {
+            Page::A4 => Size::new(8.3, 11.7),
+            Page::Legal => Size::new(8.5, 14.0),
+            Page::Letter => Size::new(8.5, 11.0),
+            Page::Tabloid => Size::new(11.0, 17.0),
+        }
+    }
+}
+
+/// A requirement the poster is sized by, instead of a width and height.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+pub enum SizeConstraint {
+    /// As large as possible on at most this many pages.
+    MaxPages(u32),
+    /// Exactly this many pages across, filled as far as they allow.
+    PagesWide(u32),
+    /// Exactly this many pages down, filled as far as they allow.
+    PagesTall(u32),
+    /// As large as possible within a wall or frame of this size, in inches.
+    Within { width: f32, height: f32 },
+}
+
+/// A poster size solved from a [`SizeConstraint`].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SolvedSize {
+    pub poster: Size,
+    /// Whether the pages are turned to landscape.
+    pub landscape: bool,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl SolvedSize {
+    pub fn page_count(&self) -> u32 {
+        self.columns * self.rows
+    }
+}
+
+impl SizeConstraint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SizeConstraint::MaxPages(_) => "At most N pages",
+            SizeConstraint::PagesWide(_) => "N pages wide",
+            SizeConstraint::PagesTall(_) => "N pages tall",
+            SizeConstraint::Within { .. } => "Fit a wall",
+        }
+    }
+
+    /// The poster size and page orientation meeting the constraint for an
+    /// image `aspect` (height over width) on portrait `page`s sharing `overlap`.
+    ///
+    /// Both orientations are tried. Page limits and walls keep whichever gives
+    /// the larger poster, or the fewer pages if that is a tie; page counts keep
+    /// whichever wastes less paper. `None` if nothing fits, e.g. when the
+    /// overlap is more than half the page.
+    pub fn solve(self, aspect: f32, page: Size, overlap: f32) -> Option<SolvedSize> {
+        [false, true]
+            .into_iter()
+            .filter_map(|landscape| {
+                let page = if landscape { page.turned() } else { page };
+                let poster = self.solve_on(aspect, page, overlap)?;
+                let layout = TileLayout::new(poster, page, overlap);
+                Some(SolvedSize {
+                    poster,
+                    landscape,
+                    columns: layout.columns,
+                    rows: layout.rows,
+                })
+            })
+            .reduce(|best, next| {
+                let area = |solved: &SolvedSize| solved.poster.width * solved.poster.height;
+                let better = match self {
+                    SizeConstraint::MaxPages(_) | SizeConstraint::Within { .. } => {
+                        area(&next) > area(&best) * 1.0001
+                            || (area(&next) >= area(&best) * 0.9999
+                                && next.page_count() < best.page_count())
+                    }
+                    // Pages are the same size either way round, so the paper
+                    // used grows with the page count.
+                    SizeConstraint::PagesWide(_) | SizeConstraint::PagesTall(_) => {
+                        area(&next) / next.page_count() as f32
+                            > area(&best) / best.page_count() as f32
+                    }
+                };
+                if better {
+                    next
+                } else {
+                    best
+                }
+            })
+    }
+
+    /// The largest poster meeting the constraint on pages of exactly `page`.
+    fn solve_on(self, aspect: f32, page: Size, overlap: f32) -> Option<Size> {
+        if let SizeConstraint::MaxPages(0)
+        | SizeConstraint::PagesWide(0)
+        | SizeConstraint::PagesTall(0) = self
+        {
+            return None;
+        }
+        // Layouts clamp a larger overlap, which would change the page pitch.
+        let usable = (0.0..=max_overlap(page)).contains(&overlap);
+        if !(usable && aspect > 0.0 && aspect.is_finite()) {
+            return None;
+        }
+        let step = Size::new(page.width - overlap, page.height - overlap);
+        // The longest side `count` pages of pitch `step` can cover.
+        let span = |count: u32, step: f32| count as f32 * step + overlap;
+        let width = match self {
+            SizeConstraint::MaxPages(pages) => (1..=pages)
+                .map(|columns| {
+                    let rows = pages / columns;
+                    span(columns, step.width).min(span(rows, step.height) / aspect)
+                })
+                .fold(0.0, f32::max),
+            SizeConstraint::PagesWide(columns) => span(columns, step.width),
+            SizeConstraint::PagesTall(rows) => span(rows, step.height) / aspect,
+            SizeConstraint::Within { width, height } => width.min(height / aspect),
+        };
+        // Stay clear of the page edges, where rounding could add a page.
+        let width = width * (1.0 - 1e-5);
+        (width > 0.0 && width.is_finite()).then(|| Size::new(width, width * aspect))
+    }
+}
+
+/// Large-format paper for printing the whole poster at a copy shop, in
+/// portrait orientation.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+pub enum Sheet {
+    A0,
+    A1,
+    /// Any roll or plotter size, in inches.
+    Custom {
+        width: f32,
+        height: f32,
+    },
+}
+
+impl Sheet {
+    pub const PRESETS: [Sheet; 2] = [Sheet::A0, Sheet::A1];
+
+    pub fn size(&self) -> Size {
+        match *self {
+            Sheet::A0 => Size::new(33.1, 46.8),
+            Sheet::A1 => Size::new(23.4, 33.1),
+            Sheet::Custom { width, height } => Size::new(width, height),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sheet::A0 => "A0",
+            Sheet::A1 => "A1",
+            Sheet::Custom { .. } => "Custom",
+        }
+    }
+}
+
+/// Roll paper, such as banner or receipt rolls, where only the width is fixed.
+/// Lengths are in inches.
+#[derive(Debug, PartialEq, Copy, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Roll {
+    pub width: f32,
+    /// Length of each page, or `None` for one continuous page per strip.
+    pub length: Option<f32>,
+    /// Distance between cut marks along the roll, if any.
+    pub cut_every: Option<f32>,
+}
+
+impl Default for Roll {
+    fn default() -> Self {
+        Self {
+            width: 24.0,
+            length: None,
+            cut_every: None,
+        }
+    }
+}
+
+impl Roll {
+    /// Whether the roll runs along the poster's width. It always follows the
+    /// longer side, so a banner prints in as few strips as possible.
+    pub fn runs_along_width(poster: Size) -> bool {
+        poster.width >= poster.height
+    }
+}
+
+/// How the image fills a poster whose aspect ratio differs from its own.
+#[derive(Debug, PartialEq, Copy, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub enum Fit {
+    /// Distort the image to the poster's shape.
+    #[default]
+    Stretch,
+    /// Show the whole image, leaving blank bars on two sides.
+    Contain,
+    /// Fill the whole poster, cutting off two sides of the image.
+    Cover,
+}
+
+impl Fit {
+    pub const ALL: [Fit; 3] = [Fit::Stretch, Fit::Contain, Fit::Cover];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fit::Stretch => "Stretch",
+            Fit::Contain => "Fit inside",
+            Fit::Cover => "Fill and crop",
+        }
+    }
+
+    /// Where an image of `image`'s proportions goes on `poster`, relative to
+    /// the poster's top-left corner. With [`Fit::Cover`] it reaches past the poster.
+    pub fn place(self, poster: Size, image: Size, anchor: Anchor) -> Rect {
+        let scale_x = poster.width / image.width;
+        let scale_y = poster.height / image.height;
+        let scale = match self {
+            Fit::Stretch => return Rect::new(0.0, 0.0, poster.width, poster.height),
+            Fit::Contain => scale_x.min(scale_y),
+            Fit::Cover => scale_x.max(scale_y),
+        };
+        let (width, height) = (image.width * scale, image.height * scale);
+        if !(width.is_finite() && height.is_finite()) {
+            return Rect::new(0.0, 0.0, poster.width, poster.height);
+        }
+        let (x, y) = anchor.fractions();
+        Rect::new(
+            (poster.width - width) * x,
+            (poster.height - height) * y,
+            width,
+            height,
+        )
+    }
+}
+
+/// The point of the poster the image is aligned to when it does not fill it exactly.
+#[derive(Debug, PartialEq, Copy, Clone, Default, serde::Deserialize, serde::Serialize)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Row by row, from the top left.
+    pub const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Center,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Anchor::TopLeft => "top left",
+            Anchor::Top => "top",
+            Anchor::TopRight => "top right",
+            Anchor::Left => "left",
+            Anchor::Center => "centre",
+            Anchor::Right => "right",
+            Anchor::BottomLeft => "bottom left",
+            Anchor::Bottom => "bottom",
+            Anchor::BottomRight => "bottom right",
+        }
+    }
+
+    /// How far across and down the poster the anchor lies, from 0 to 1.
+    fn fractions(self) -> (f32, f32) {
+        let index = Anchor::ALL
+            .iter()
+            .position(|anchor| *anchor == self)
+            .expect("every anchor is listed");
+        ((index % 3) as f32 / 2.0, (index / 3) as f32 / 2.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Size {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Size {
+    pub const fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+
+    /// The same size turned a quarter, e.g. a portrait page as landscape.
+    pub const fn turned(self) -> Self {
+        Self::new(self.height, self.width)
+    }
+}
+
+/// An axis-aligned rectangle, with `y` growing downwards.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    /// The overlapping part of both rectangles, or `None` if they do not touch.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (right > x && bottom > y).then(|| Rect::new(x, y, right - x, bottom - y))
+    }
+}
+
+/// The longest page side PDF viewers and printers accept: 14400 points.
+pub const MAX_PAGE_LENGTH: f32 = 200.0;
+
+/// The most neighbouring pages of `page` size may share: half the shorter side.
+pub fn max_overlap(page: Size) -> f32 {
+    page.width.min(page.height) / 2.0
+}
+
+/// Number of pages needed to cover `desired`, where neighbouring pages share `overlap`.
+pub fn calculate_page_count(desired: f32, print: f32, overlap: f32) -> u32 {
+    ((desired - overlap) / (print - overlap)).ceil().max(1.0) as u32
+}
+
+/// Blank space between the edge of the page grid and the centred poster.
+pub fn calculate_margin(desired: f32, print: f32, overlap: f32) -> f32 {
+    let page_count = calculate_page_count(desired, print, overlap) as f32;
+    (page_count * (print - overlap) + overlap - desired) / 2.0
+}
+
+/// Pixels per inch, horizontally and vertically, when an image of
+/// `pixel_width` x `pixel_height` is printed at `poster` size.
+pub fn effective_dpi(pixel_width: u32, pixel_height: u32, poster: Size) -> (f32, f32) {
+    (
+        pixel_width as f32 / poster.width,
+        pixel_height as f32 / poster.height,
+    )
+}
+
+/// One printed page of a [`TileLayout`].
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Tile {
+    pub column: u32,
+    pub row: u32,
+    /// The area of the poster the page covers, relative to the poster's top-left
+    /// corner. Pages on the edge of the grid extend past the poster.
+    pub page: Rect,
+    /// The part of `page` that the poster actually fills.
+    pub content: Rect,
+    /// The part of `content` the image covers. Smaller than `content` where
+    /// the image is fitted inside the poster with blank bars.
+    pub image: Rect,
+    /// `image` in normalised image coordinates, from 0 to 1.
+    pub uv: Rect,
+}
+
+impl Tile {
+    /// The page's name in the grid: a row letter and a column number, e.g. `B3`.
+    pub fn label(&self) -> String {
+        format!("{}{}", row_label(self.row), self.column + 1)
+    }
+}
+
+/// Letters naming a row of pages: `A` to `Z`, then `AA`, `AB` and so on.
+pub fn row_label(row: u32) -> String {
+    let mut label = Vec::new();
+    let mut n = row + 1;
+    while n > 0 {
+        n -= 1;
+        label.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).expect("row labels are ASCII")
+}
+
+/// The grid of pages a poster is split into.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TileLayout {
+    pub poster: Size,
+    pub page: Size,
+    pub overlap: f32,
+    pub columns: u32,
+    pub rows: u32,
+    /// Blank space between the grid edge and the poster, on each side.
+    pub margin: Size,
+    /// The roll the pages are printed on, if they are not cut sheets.
+    pub roll: Option<Roll>,
+    /// Where the image lies, relative to the poster's top-left corner. The
+    /// whole poster unless the image is fitted to it, see [`Fit::place`].
+    pub image: Rect,
+}
+
+impl TileLayout {
+    /// Lays the poster out on `page`s. An `overlap` beyond [`max_overlap`] is
+    /// clamped to it, so every page still advances across the poster.
+    pub fn new(poster: Size, page: Size, overlap: f32) -> Self {
+        let overlap = overlap.clamp(0.0, max_overlap(page));
+        Self {
+            poster,
+            page,
+            overlap,
+            columns: calculate_page_count(poster.width, page.width, overlap),
+            rows: calculate_page_count(poster.height, page.height, overlap),
+            margin: Size::new(
+                calculate_margin(poster.width, page.width, overlap),
+                calculate_margin(poster.height, page.height, overlap),
+            ),
+            roll: None,
+            image: Rect::new(0.0, 0.0, poster.width, poster.height),
+        }
+    }
+
+    /// Puts the image at `image` instead of stretching it over the whole poster.
+    pub fn with_image(mut self, image: Rect) -> Self {
+        self.image = image;
+        self
+    }
+
+    /// Lays the poster out in strips across `roll`. Pages have the roll's
+    /// length, or without one are a single continuous page per strip. Either
+    /// way no page is longer than [`MAX_PAGE_LENGTH`]; longer strips are split.
+    pub fn on_roll(poster: Size, roll: Roll, overlap: f32) -> Self {
+        let along_width = Roll::runs_along_width(poster);
+        let length = roll
+            .length
+            .unwrap_or(if along_width {
+                poster.width
+            } else {
+                poster.height
+            })
+            .min(MAX_PAGE_LENGTH);
+        let page = if along_width {
+            Size::new(length, roll.width)
+        } else {
+            Size::new(roll.width, length)
+        };
+        Self {
+            roll: Some(roll),
+            ..Self::new(poster, page, overlap)
+        }
+    }
+
+    /// Lays the poster out on large-format sheets without overlap, turned to
+    /// landscape if that needs fewer of them. Usually one sheet is enough.
+    pub fn on_sheet(poster: Size, sheet: Size) -> Self {
+        let portrait = Self::new(poster, sheet, 0.0);
+        let landscape = Self::new(poster, Size::new(sheet.height, sheet.width), 0.0);
+        if landscape.page_count() < portrait.page_count() {
+            landscape
+        } else {
+            portrait
+        }
+    }
+
+    pub fn page_count(&self) -> u32 {
+        self.columns.saturating_mul(self.rows)
+    }
+
+    /// Distance between the top-left corners of neighbouring pages.
+    pub fn step(&self) -> Size {
+        Size::new(
+            self.page.width - self.overlap,
+            self.page.height - self.overlap,
+        )
+    }
+
+    pub fn tile(&self, column: u32, row: u32) -> Tile {
+        let step = self.step();
+        let page = Rect::new(
+            column as f32 * step.width - self.margin.width,
+            row as f32 * step.height - self.margin.height,
+            self.page.width,
+            self.page.height,
+        );
+        let poster = Rect::new(0.0, 0.0, self.poster.width, self.poster.height);
+        let content = page
+            .intersect(&poster)
+            .unwrap_or(Rect::new(page.x, page.y, 0.0, 0.0));
+        let image = content
+            .intersect(&self.image)
+            .unwrap_or(Rect::new(content.x, content.y, 0.0, 0.0));
+        let uv = Rect::new(
+            (image.x - self.image.x) / self.image.width,
+            (image.y - self.image.y) / self.image.height,
+            image.width / self.image.width,
+            image.height / self.image.height,
+        );
+        Tile {
+            column,
+            row,
+            page,
+            content,
+            image,
+            uv,
+        }
+    }
+
+    /// All tiles, row by row.
+    pub fn tiles(&self) -> impl Iterator<Item = Tile> + '_ {
+        (0..self.rows)
+            .flat_map(move |row| (0..self.columns).map(move |column| self.tile(column, row)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlap_is_clamped_to_half_the_page() {
+        let poster = Size::new(100.0, 100.0);
+        for overlap in [8.0, 10.0, 50.0] {
+            let layout = TileLayout::new(poster, Size::new(10.0, 8.0), overlap);
+            assert_eq!(layout.overlap, 4.0);
+            assert_eq!(layout.columns, 16);
+            assert_eq!(layout.rows, 24);
+        }
+    }
+
+    #[test]
+    fn continuous_roll_strips_are_split_at_the_pdf_limit() {
+        let roll = Roll {
+            width: 24.0,
+            ..Roll::default()
+        };
+        let layout = TileLayout::on_roll(Size::new(500.0, 40.0), roll, 0.0);
+        assert_eq!(layout.page, Size::new(MAX_PAGE_LENGTH, 24.0));
+        assert_eq!((layout.columns, layout.rows), (3, 2));
+
+        let short = TileLayout::on_roll(Size::new(150.0, 40.0), roll, 0.0);
+        assert_eq!(short.page, Size::new(150.0, 24.0));
+        assert_eq!(short.columns, 1);
+    }
+
+    const LETTER: Size = Size::new(8.5, 11.0);
+
+    #[test]
+    fn page_counts_are_met_exactly() {
+        for count in 1..=12 {
+            for aspect in [0.3, 1.0, 2.5] {
+                let wide = SizeConstraint::PagesWide(count)
+                    .solve(aspect, LETTER, 0.5)
+                    .unwrap();
+                assert_eq!(wide.columns, count, "{count} wide at {aspect}");
+                let tall = SizeConstraint::PagesTall(count)
+                    .solve(aspect, LETTER, 0.5)
+                    .unwrap();
+                assert_eq!(tall.rows, count, "{count} tall at {aspect}");
+            }
+        }
+    }
+
+    #[test]
+    fn page_limit_is_never_exceeded() {
+        for pages in 1..=30 {
+            for aspect in [0.25, 0.75, 1.0, 1.5, 4.0] {
+                let solved = SizeConstraint::MaxPages(pages)
+                    .solve(aspect, LETTER, 0.25)
+                    .unwrap();
+                assert!(solved.page_count() <= pages, "{pages} pages at {aspect}");
+                assert!((solved.poster.height / solved.poster.width - aspect).abs() < 1e-4);
+            }
+        }
+        // A letter-shaped image fills four portrait pages exactly.
+        let solved = SizeConstraint::MaxPages(4)
+            .solve(11.0 / 8.5, LETTER, 0.0)
+            .unwrap();
+        assert!(!solved.landscape);
+        assert_eq!((solved.columns, solved.rows), (2, 2));
+        assert!((solved.poster.width - 17.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn exact_multiples_do_not_round_up() {
+        // Three pages with half an inch of overlap cover exactly 24.5 inches.
+        assert_eq!(calculate_page_count(24.5, 8.5, 0.5), 3);
+        let solved = SizeConstraint::Within {
+            width: 24.5,
+            height: 100.0,
+        }
+        .solve(1.0, Size::new(8.5, 8.5), 0.5)
+        .unwrap();
+        assert!((solved.poster.width - 24.5).abs() < 1e-3);
+        assert_eq!((solved.columns, solved.rows), (3, 3));
+    }
+
+    #[test]
+    fn wall_size_bounds_the_poster() {
+        for (width, height) in [(78.74, 59.06), (20.0, 100.0), (100.0, 20.0)] {
+            let solved = SizeConstraint::Within { width, height }
+                .solve(0.75, LETTER, 0.5)
+                .unwrap();
+            let poster = solved.poster;
+            assert!(poster.width <= width && poster.height <= height);
+            assert!(poster.width > width * 0.999 || poster.height > height * 0.999);
+            let layout = TileLayout::new(poster, LETTER, 0.5);
+            let turned = TileLayout::new(poster, LETTER.turned(), 0.5);
+            assert_eq!(
+                solved.page_count(),
+                layout.page_count().min(turned.page_count())
+            );
+        }
+    }
+
+    #[test]
+    fn overlap_leaving_no_room_has_no_solution() {
+        let constraints = [
+            SizeConstraint::MaxPages(4),
+            SizeConstraint::PagesWide(2),
+            SizeConstraint::PagesTall(2),
+            SizeConstraint::Within {
+                width: 40.0,
+                height: 40.0,
+            },
+        ];
+        for constraint in constraints {
+            for overlap in [4.5, 8.5, 11.0, 20.0] {
+                assert_eq!(constraint.solve(1.0, LETTER, overlap), None);
+            }
+            assert_eq!(constraint.solve(1.0, LETTER, 4.25).map(|_| ()), Some(()));
+        }
+        assert_eq!(SizeConstraint::MaxPages(0).solve(1.0, LETTER, 0.0), None);
+    }
+
+    fn inches(text: &str) -> f32 {
+        match parse_length(text, Units::Centimeters) {
+            Ok(LengthInput::Inches(inches)) => inches,
+            other => panic!("{text}: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lengths_are_parsed_in_every_unit() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(close(inches("2.54"), 1.0));
+        assert!(close(inches("12in"), 12.0));
+        assert!(close(inches("1 inch"), 1.0));
+        assert!(close(inches("2 inches"), 2.0));
+        assert!(close(inches("3\""), 3.0));
+        assert!(close(inches("2ft"), 24.0));
+        assert!(close(inches("1'"), 12.0));
+        assert!(close(inches("72pt"), 1.0));
+        assert!(close(inches("25.4 mm"), 1.0));
+        assert!(close(inches("127CM"), 50.0));
+        assert!(close(inches("1.27m"), 50.0));
+        assert!(close(inches("47 1/4 in"), 47.25));
+        assert!(close(inches("3/4in"), 0.75));
+        assert_eq!(
+            parse_length("150%", Units::Inches),
+            Ok(LengthInput::Percent(150.0))
+        );
+    }
+
+    #[test]
+    fn malformed_lengths_are_rejected() {
+        for text in [
+            "", "cm", "abc", "1/0 in", "1/2/3", "1 2 3", "1/2 3/4", "1e999",
+        ] {
+            assert_eq!(
+                parse_length(text, Units::Inches),
+                Err(ParseLengthError::Invalid(text.to_owned())),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_length("5 parsecs", Units::Inches),
+            Err(ParseLengthError::UnknownUnit("parsecs".to_owned()))
+        );
+    }
+
+    #[test]
+    fn signed_lengths_may_be_zero_or_negative() {
+        for (text, expected) in [("0", 0.0), ("-2.54cm", -1.0), ("-1/2 in", -0.5)] {
+            assert_eq!(
+                parse_signed_length(text, Units::Inches),
+                Ok(LengthInput::Inches(expected)),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn lengths_must_be_positive() {
+        for text in ["-5cm", "0", "0 in", "-1/2", "-0.5m", "0%", "-20%"] {
+            assert_eq!(
+                parse_length(text, Units::Inches),
+                Err(ParseLengthError::NotPositive(text.to_owned())),
+                "{text}"
+            );
+        }
+    }
+}
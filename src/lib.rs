@@ -1,4 +1,23 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+//! Splits large images into printable, tileable PDF posters.
+//!
+//! The [`layout`] and [`export`] modules hold the tiling engine and do not
+//! depend on egui; [`EtracerApp`] is the GUI built on top of them.
+
 mod app;
+pub mod banner;
+pub mod caption;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
+pub mod error;
+pub mod export;
+mod history;
+pub mod layout;
+pub mod overlay;
+mod pdf_update;
+pub mod project;
+pub mod resample;
+pub mod source;
+mod tiled_texture;
 pub use app::EtracerApp;
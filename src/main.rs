@@ -6,6 +6,18 @@
 fn main() -> eframe::Result {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
+    // `tile` or a flag selects the headless command-line interface; anything
+    // else is a file to open in the GUI.
+    let first = std::env::args_os().nth(1);
+    if first
+        .as_ref()
+        .and_then(|first| first.to_str())
+        .is_some_and(etracer::cli::is_command)
+    {
+        attach_console();
+        std::process::exit(etracer::cli::run(std::env::args_os().skip(1)));
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([400.0, 300.0])
@@ -20,10 +32,34 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "eframe template",
         native_options,
-        Box::new(|cc| Ok(Box::new(etracer::EtracerApp::new(cc)))),
+        Box::new(|cc| {
+            let app = etracer::EtracerApp::new(cc);
+            if let Some(path) = first {
+                app.open_path(&cc.egui_ctx, path.into());
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
 
+/// Lets the command line print to the console it was started from. Release
+/// builds on Windows are GUI programs, which get no console of their own.
+#[cfg(all(windows, not(debug_assertions)))]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // Fails harmlessly when there is no parent console, e.g. from Explorer.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(all(windows, not(debug_assertions)))))]
+fn attach_console() {}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -0,0 +1,569 @@
+//! Printing aids drawn over the tiles: crop marks, page labels, a grid, a
+//! calibration ruler and the poster outline.
+//!
+//! Each kind of overlay goes into its own optional content group, a layer
+//! viewers can hide before printing. krilla cannot write those, so the
+//! overlays are added as plain content streams in an incremental update.
+
+use std::fmt::Write;
+
+use crate::error::Error;
+use crate::export::POINTS_PER_INCH;
+use crate::layout::{Roll, Tile, TileLayout};
+use crate::pdf_update::{parse_dictionary, write_dictionary, PdfUpdate};
+
+/// Which overlays an export draws.
+#[derive(Debug, PartialEq, Copy, Clone, Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Overlays {
+    /// Marks where to trim each page before gluing, and the cut marks along
+    /// roll pages.
+    pub crop_marks: bool,
+    /// The page's name in the grid, e.g. `B3`.
+    pub labels: bool,
+    /// Lines every inch, continuing across pages.
+    pub grid: bool,
+    /// A ruler on the first page to check the print scale.
+    pub ruler: bool,
+    /// The edge of the poster.
+    pub outline: bool,
+}
+
+/// One kind of overlay, and one layer in the PDF.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Overlay {
+    CropMarks,
+    Labels,
+    Grid,
+    Ruler,
+    Outline,
+}
+
+impl Overlay {
+    pub const ALL: [Overlay; 5] = [
+        Overlay::CropMarks,
+        Overlay::Labels,
+        Overlay::Grid,
+        Overlay::Ruler,
+        Overlay::Outline,
+    ];
+
+    /// The layer name viewers show.
+    pub fn name(self) -> &'static str {
+        match self {
+            Overlay::CropMarks => "Crop marks",
+            Overlay::Labels => "Page labels",
+            Overlay::Grid => "Grid",
+            Overlay::Ruler => "Calibration ruler",
+            Overlay::Outline => "Poster outline",
+        }
+    }
+}
+
+impl Overlays {
+    pub fn is_enabled(&self, overlay: Overlay) -> bool {
+        match overlay {
+            Overlay::CropMarks => self.crop_marks,
+            Overlay::Labels => self.labels,
+            Overlay::Grid => self.grid,
+            Overlay::Ruler => self.ruler,
+            Overlay::Outline => self.outline,
+        }
+    }
+
+    pub fn set(&mut self, overlay: Overlay, enabled: bool) {
+        let flag = match overlay {
+            Overlay::CropMarks => &mut self.crop_marks,
+            Overlay::Labels => &mut self.labels,
+            Overlay::Grid => &mut self.grid,
+            Overlay::Ruler => &mut self.ruler,
+            Overlay::Outline => &mut self.outline,
+        };
+        *flag = enabled;
+    }
+
+    pub fn enabled(self) -> impl Iterator<Item = Overlay> {
+        Overlay::ALL
+            .into_iter()
+            .filter(move |overlay| self.is_enabled(*overlay))
+    }
+}
+
+/// Resource name of the label font on every page.
+const FONT: &str = "EtracerHelvetica";
+
+/// Draws the enabled overlays onto the pages of `pdf`, which must be laid out
+/// as `layout` describes, one tile per page row by row.
+pub(crate) fn add_layers(
+    pdf: &mut PdfUpdate,
+    layout: &TileLayout,
+    overlays: Overlays,
+) -> Result<(), Error> {
+    let layers: Vec<(Overlay, u32)> = overlays
+        .enabled()
+        .map(|overlay| {
+            let id = pdf.add_object(format!("<< /Type /OCG /Name ({}) >>", overlay.name()));
+            (overlay, id)
+        })
+        .collect();
+    if layers.is_empty() {
+        return Ok(());
+    }
+    let refs = layers
+        .iter()
+        .map(|(_, id)| format!("{id} 0 R"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    pdf.set_catalog_entry(&format!(
+        "/OCProperties << /OCGs [{refs}] /D << /Name (Overlays) /Order [{refs}] /ON [{refs}] >> >>"
+    ));
+    let properties = layers
+        .iter()
+        .enumerate()
+        .map(|(i, (_, id))| format!("/oc{i} {id} 0 R"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let font = pdf.add_object(
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_owned(),
+    );
+    // krilla's content may leave the graphics state changed, so it is wrapped
+    // in a save and restore before the overlays draw in plain PDF coordinates.
+    let save = pdf.add_object(stream("q\n"));
+    let restore = pdf.add_object(stream("Q\n"));
+
+    for (index, page_id) in pdf.page_ids()?.into_iter().enumerate() {
+        let index = index as u32;
+        let tile = layout.tile(index % layout.columns, index / layout.columns);
+        let mut contents = Vec::new();
+        for (i, (overlay, _)) in layers.iter().enumerate() {
+            let ops = draw(*overlay, layout, &tile);
+            if !ops.is_empty() {
+                contents.push(pdf.add_object(stream(&format!("/OC /oc{i} BDC\nq\n{ops}Q\nEMC\n"))));
+            }
+        }
+        if contents.is_empty() {
+            continue;
+        }
+        let page = add_to_page(
+            pdf.object(page_id)?,
+            save,
+            restore,
+            &contents,
+            &properties,
+            font,
+        )?;
+        pdf.replace_object(page_id, page);
+    }
+    Ok(())
+}
+
+/// An uncompressed stream object holding `content`.
+fn stream(content: &str) -> String {
+    format!(
+        "<< /Length {} >>\nstream\n{content}\nendstream",
+        content.len()
+    )
+}
+
+/// `page` with the overlay streams appended to its contents and their
+/// resources added. Expects the inline resource dictionary krilla writes, and
+/// fails rather than leave a page without its overlays.
+fn add_to_page(
+    page: &str,
+    save: u32,
+    restore: u32,
+    contents: &[u32],
+    properties: &str,
+    font: u32,
+) -> Result<String, Error> {
+    let malformed = |reason: &str| Error::PdfUpdate(format!("page: {reason}"));
+    let mut page = parse_dictionary(page)?;
+
+    let original = value_mut(&mut page, "/Contents").ok_or_else(|| malformed("no /Contents"))?;
+    let references = match original.strip_prefix('[') {
+        Some(array) => array.strip_suffix(']').unwrap_or(array).trim(),
+        None if original.ends_with(" R") => original.as_str(),
+        None => return Err(malformed("/Contents is not a stream or an array")),
+    };
+    let mut new_contents = format!("[{save} 0 R {references} {restore} 0 R");
+    for id in contents {
+        let _ = write!(new_contents, " {id} 0 R");
+    }
+    new_contents.push(']');
+    *original = new_contents;
+
+    let resources = value_mut(&mut page, "/Resources").ok_or_else(|| malformed("no /Resources"))?;
+    if !resources.starts_with("<<") {
+        return Err(malformed("resources are not inline"));
+    }
+    let mut entries = parse_dictionary(resources)?;
+    if value_mut(&mut entries, "/Properties").is_some() {
+        return Err(malformed("resources already have /Properties"));
+    }
+    let font_entry = (format!("/{FONT}"), format!("{font} 0 R"));
+    match value_mut(&mut entries, "/Font") {
+        Some(fonts) if fonts.starts_with("<<") => {
+            let mut fonts_entries = parse_dictionary(fonts)?;
+            fonts_entries.push(font_entry);
+            *fonts = write_dictionary(&fonts_entries, 2);
+        }
+        Some(_) => return Err(malformed("fonts are not inline")),
+        None => entries.push(("/Font".to_owned(), write_dictionary(&[font_entry], 2))),
+    }
+    entries.push(("/Properties".to_owned(), format!("<< {properties} >>")));
+    *resources = write_dictionary(&entries, 1);
+    Ok(write_dictionary(&page, 0))
+}
+
+/// The value of `key` in dictionary `entries`.
+fn value_mut<'a>(entries: &'a mut [(String, String)], key: &str) -> Option<&'a mut String> {
+    entries
+        .iter_mut()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value)
+}
+
+/// Content stream operators drawing `overlay` on `tile`, empty if there is
+/// nothing to draw on this page.
+fn draw(overlay: Overlay, layout: &TileLayout, tile: &Tile) -> String {
+    let mut ops = Ops::new(tile.page.height);
+    match overlay {
+        Overlay::CropMarks => crop_marks(&mut ops, layout, tile),
+        Overlay::Labels => label(&mut ops, layout, tile),
+        Overlay::Grid => grid(&mut ops, tile),
+        Overlay::Ruler => {
+            if (tile.column, tile.row) == (0, 0) {
+                ruler(&mut ops, layout, tile);
+            }
+        }
+        Overlay::Outline => outline(&mut ops, layout, tile),
+    }
+    ops.0
+}
+
+/// Marks smaller than this are left out as rounding noise.
+const EPSILON: f32 = 0.001;
+
+/// Where the page is cut before gluing, in inches from its top-left corner:
+/// left, top, right and bottom.
+///
+/// Every page but those in the first row and column loses its overlap at the
+/// top and left and is glued over its neighbours; blank margins are cut off
+/// the edge pages.
+fn trim(layout: &TileLayout, tile: &Tile) -> (f32, f32, f32, f32) {
+    let left = if tile.column > 0 {
+        layout.overlap
+    } else {
+        tile.content.x - tile.page.x
+    };
+    let top = if tile.row > 0 {
+        layout.overlap
+    } else {
+        tile.content.y - tile.page.y
+    };
+    (
+        left,
+        top,
+        tile.content.right() - tile.page.x,
+        tile.content.bottom() - tile.page.y,
+    )
+}
+
+fn crop_marks(ops: &mut Ops, layout: &TileLayout, tile: &Tile) {
+    let (width, height) = (tile.page.width, tile.page.height);
+    let (left, top, right, bottom) = trim(layout, tile);
+    let length = 0.4;
+    ops.stroke_style(0.5, 0.0);
+    for x in [left, right] {
+        if x > EPSILON && x < width - EPSILON {
+            ops.line(x, 0.0, x, length);
+            ops.line(x, height, x, height - length);
+        }
+    }
+    for y in [top, bottom] {
+        if y > EPSILON && y < height - EPSILON {
+            ops.line(0.0, y, length, y);
+            ops.line(width, y, width - length, y);
+        }
+    }
+
+    let Some(every) = layout
+        .roll
+        .and_then(|roll| roll.cut_every)
+        .filter(|every| *every > 0.0)
+    else {
+        return;
+    };
+    // Cuts are measured from the start of the poster, so they continue across pages.
+    let along_width = Roll::runs_along_width(layout.poster);
+    let (start, end, offset) = if along_width {
+        (tile.content.x, tile.content.right(), tile.page.x)
+    } else {
+        (tile.content.y, tile.content.bottom(), tile.page.y)
+    };
+    let mut cut = ((start / every).floor() + 1.0) * every;
+    while cut < end - EPSILON {
+        let at = cut - offset;
+        if along_width {
+            ops.line(at, 0.0, at, length);
+            ops.line(at, height, at, height - length);
+        } else {
+            ops.line(0.0, at, length, at);
+            ops.line(width, at, width - length, at);
+        }
+        cut += every;
+    }
+}
+
+fn label(ops: &mut Ops, layout: &TileLayout, tile: &Tile) {
+    let (left, top, ..) = trim(layout, tile);
+    let size = 12.0;
+    let x = left + 0.15;
+    let y = top + 0.15 + size / POINTS_PER_INCH;
+    ops.text(x, y, size, 0.4, &tile.label());
+}
+
+fn grid(ops: &mut Ops, tile: &Tile) {
+    let content = tile.content;
+    if content.width <= 0.0 || content.height <= 0.0 {
+        return;
+    }
+    ops.stroke_style(0.25, 0.6);
+    // Lines sit at whole inches of the poster, so they line up across pages.
+    let mut x = content.x.ceil();
+    while x <= content.right() + EPSILON {
+        ops.line(
+            x - tile.page.x,
+            content.y - tile.page.y,
+            x - tile.page.x,
+            content.bottom() - tile.page.y,
+        );
+        x += 1.0;
+    }
+    let mut y = content.y.ceil();
+    while y <= content.bottom() + EPSILON {
+        ops.line(
+            content.x - tile.page.x,
+            y - tile.page.y,
+            content.right() - tile.page.x,
+            y - tile.page.y,
+        );
+        y += 1.0;
+    }
+}
+
+/// A 3 inch and a 5 cm scale in the bottom left corner of the trimmed page.
+fn ruler(ops: &mut Ops, layout: &TileLayout, tile: &Tile) {
+    let (left, _, _, bottom) = trim(layout, tile);
+    let x0 = left + 0.4;
+    // Kept clear of the print reminder in the corner.
+    let inch_y = bottom - 1.3;
+    let cm_y = bottom - 0.75;
+    let cm = 1.0 / 2.54;
+
+    ops.fill_rect(x0 - 0.1, inch_y - 0.3, 3.8, 1.1, 1.0);
+    ops.stroke_style(0.5, 0.0);
+    ops.line(x0, inch_y, x0 + 3.0, inch_y);
+    for quarter in 0..=12 {
+        let x = x0 + quarter as f32 * 0.25;
+        let length = if quarter % 4 == 0 { 0.2 } else { 0.1 };
+        ops.line(x, inch_y, x, inch_y - length);
+        if quarter % 4 == 0 {
+            ops.text(
+                x + 0.03,
+                inch_y - 0.12,
+                7.0,
+                0.0,
+                &(quarter / 4).to_string(),
+            );
+        }
+    }
+    ops.text(x0 + 3.1, inch_y, 7.0, 0.0, "in");
+
+    ops.line(x0, cm_y, x0 + 5.0 * cm, cm_y);
+    for mm in 0..=50 {
+        let x = x0 + mm as f32 * cm / 10.0;
+        let length = match mm {
+            _ if mm % 10 == 0 => 0.2,
+            _ if mm % 5 == 0 => 0.14,
+            _ => 0.07,
+        };
+        ops.line(x, cm_y, x, cm_y - length);
+        if mm % 10 == 0 {
+            ops.text(x + 0.03, cm_y - 0.12, 7.0, 0.0, &(mm / 10).to_string());
+        }
+    }
+    ops.text(x0 + 5.0 * cm + 0.1, cm_y, 7.0, 0.0, "cm");
+    ops.text(
+        x0,
+        cm_y + 0.2,
+        7.0,
+        0.0,
+        "Printed at actual size, these scales measure exactly 3 in and 5 cm.",
+    );
+}
+
+/// The poster's edges, where they cross this page.
+fn outline(ops: &mut Ops, layout: &TileLayout, tile: &Tile) {
+    let content = tile.content;
+    if content.width <= 0.0 || content.height <= 0.0 {
+        return;
+    }
+    let (x0, y0) = (content.x - tile.page.x, content.y - tile.page.y);
+    let (x1, y1) = (x0 + content.width, y0 + content.height);
+    ops.stroke_style(0.75, 0.0);
+    if content.x <= EPSILON {
+        ops.line(x0, y0, x0, y1);
+    }
+    if content.y <= EPSILON {
+        ops.line(x0, y0, x1, y0);
+    }
+    if content.right() >= layout.poster.width - EPSILON {
+        ops.line(x1, y0, x1, y1);
+    }
+    if content.bottom() >= layout.poster.height - EPSILON {
+        ops.line(x0, y1, x1, y1);
+    }
+}
+
+/// Content stream operators, taking positions in inches from the page's
+/// top-left corner like the rest of the layout code.
+struct Ops(String, f32);
+
+impl Ops {
+    fn new(page_height: f32) -> Self {
+        Self(String::new(), page_height)
+    }
+
+    /// PDF user space, which starts at the bottom left.
+    fn point(&self, x: f32, y: f32) -> (f32, f32) {
+        (x * POINTS_PER_INCH, (self.1 - y) * POINTS_PER_INCH)
+    }
+
+    /// Sets the line width in points and the grey level of strokes.
+    fn stroke_style(&mut self, width: f32, gray: f32) {
+        let _ = writeln!(self.0, "{width} w {gray} G [] 0 d");
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        let (x1, y1) = self.point(x1, y1);
+        let (x2, y2) = self.point(x2, y2);
+        let _ = writeln!(self.0, "{x1:.2} {y1:.2} m {x2:.2} {y2:.2} l S");
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, gray: f32) {
+        let (x, y) = self.point(x, y + height);
+        let _ = writeln!(
+            self.0,
+            "{gray} g {x:.2} {y:.2} {:.2} {:.2} re f",
+            width * POINTS_PER_INCH,
+            height * POINTS_PER_INCH
+        );
+    }
+
+    /// Writes ASCII `text` with its baseline starting at `x`, `y`.
+    fn text(&mut self, x: f32, y: f32, size: f32, gray: f32, text: &str) {
+        let (x, y) = self.point(x, y);
+        let escaped = text
+            .replace('\\', "\\\\")
+            .replace('(', "\\(")
+            .replace(')', "\\)");
+        let _ = writeln!(
+            self.0,
+            "BT /{FONT} {size} Tf {gray} g {x:.2} {y:.2} Td ({escaped}) Tj ET"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caption::CaptionFont;
+    use krilla::geom::Point;
+    use krilla::path::Fill;
+    use krilla::surface::TextDirection;
+    use krilla::{Document, PageSettings};
+
+    /// Two pages as krilla writes them, the first with text so it has fonts.
+    fn krilla_pdf() -> PdfUpdate {
+        let mut doc = Document::new();
+        let mut page = doc.start_page_with(PageSettings::new(200.0, 100.0));
+        let mut surface = page.surface();
+        surface.fill_text(
+            Point::from_xy(10.0, 50.0),
+            Fill::default(),
+            CaptionFont::Sans.load().unwrap(),
+            12.0,
+            &[],
+            "A1",
+            false,
+            TextDirection::Auto,
+        );
+        surface.finish();
+        page.finish();
+        doc.start_page_with(PageSettings::new(200.0, 100.0))
+            .finish();
+        PdfUpdate::new(doc.finish().unwrap()).unwrap()
+    }
+
+    fn value<'a>(entries: &'a [(String, String)], key: &str) -> &'a str {
+        let entry = entries.iter().find(|(name, _)| name == key);
+        &entry.unwrap_or_else(|| panic!("no {key}")).1
+    }
+
+    #[test]
+    fn overlays_are_added_to_krilla_pages() {
+        let pdf = krilla_pdf();
+        for (index, id) in pdf.page_ids().unwrap().into_iter().enumerate() {
+            let original = parse_dictionary(pdf.object(id).unwrap()).unwrap();
+            let page = add_to_page(
+                pdf.object(id).unwrap(),
+                90,
+                91,
+                &[92, 93],
+                "/oc0 94 0 R",
+                95,
+            )
+            .unwrap();
+            let page = parse_dictionary(&page).unwrap();
+            assert_eq!(
+                value(&page, "/Contents"),
+                format!(
+                    "[90 0 R {} 91 0 R 92 0 R 93 0 R]",
+                    value(&original, "/Contents")
+                )
+            );
+            assert_eq!(value(&page, "/MediaBox"), value(&original, "/MediaBox"));
+
+            let resources = parse_dictionary(value(&page, "/Resources")).unwrap();
+            let properties = parse_dictionary(value(&resources, "/Properties")).unwrap();
+            assert_eq!(value(&properties, "/oc0"), "94 0 R");
+            let fonts = parse_dictionary(value(&resources, "/Font")).unwrap();
+            assert_eq!(value(&fonts, "/EtracerHelvetica"), "95 0 R");
+            // The text on the first page keeps its own font.
+            assert_eq!(fonts.len(), if index == 0 { 2 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn unexpected_pages_are_an_error() {
+        let add = |page: &str| add_to_page(page, 1, 2, &[3], "/oc0 4 0 R", 5);
+        let pages = [
+            "<< /Type /Page /Resources << >> >>",
+            "<< /Type /Page /Contents 7 /Resources << >> >>",
+            "<< /Type /Page /Contents 7 0 R >>",
+            "<< /Type /Page /Contents 7 0 R /Resources 8 0 R >>",
+            "<< /Type /Page /Contents 7 0 R /Resources << /Font 9 0 R >> >>",
+            "<< /Type /Page /Contents 7 0 R /Resources << /Properties << >> >> >>",
+            "<< /Type /Page /Contents 7 0 R /Resources <<",
+        ];
+        for page in pages {
+            assert!(matches!(add(page), Err(Error::PdfUpdate(_))), "{page}");
+        }
+        // `/ContentsX` is not `/Contents`.
+        assert!(add("<< /ContentsX 7 0 R /Resources << >> >>").is_err());
+        assert!(add("<< /Contents [7 0 R 8 0 R] /Resources << >> >>").is_ok());
+    }
+}
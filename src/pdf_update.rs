@@ -0,0 +1,367 @@
+//! Incremental updates to finished PDFs, for what krilla cannot write itself.
+//!
+//! The original file is left untouched. New and replaced objects, a new
+//! catalog and a cross-reference section pointing back at the old one are
+//! appended, which every PDF reader understands.
+
+use std::fmt::Write;
+
+use crate::error::Error;
+
+/// Collects additions to a PDF and appends them in one update.
+pub(crate) struct PdfUpdate {
+    pdf: Vec<u8>,
+    /// Byte offset of every object in the original file, by object number.
+    offsets: Vec<Option<usize>>,
+    root: u32,
+    /// The catalog dictionary without its closing `>>`.
+    catalog: String,
+    next_id: u32,
+    objects: Vec<(u32, String)>,
+    /// Entries of the previous trailer other than `/Size`, `/Root` and
+    /// `/Prev`, such as `/Info` and `/ID`. An update's trailer must repeat them.
+    trailer: Vec<(String, String)>,
+    previous_xref: usize,
+}
+
+impl PdfUpdate {
+    pub fn new(pdf: Vec<u8>) -> Result<Self, Error> {
+        let tail = String::from_utf8_lossy(&pdf[pdf.len().saturating_sub(1024)..]);
+        let previous_xref: usize = tail
+            .rfind("startxref")
+            .and_then(|start| name_value(&tail[start..], "startxref"))
+            .and_then(|offset| offset.parse().ok())
+            .ok_or_else(|| malformed("no startxref"))?;
+
+        let xref = pdf
+            .get(previous_xref..)
+            .map(String::from_utf8_lossy)
+            .ok_or_else(|| malformed("startxref points past the end"))?;
+        let trailer_start = xref
+            .find("trailer")
+            .ok_or_else(|| malformed("no trailer"))?;
+        let offsets = parse_xref(&xref[..trailer_start])?;
+        let mut trailer = parse_dictionary(&xref[trailer_start + "trailer".len()..])?;
+        let mut take = |key: &str| {
+            let index = trailer.iter().position(|(name, _)| name == key)?;
+            Some(trailer.remove(index).1)
+        };
+        let size: u32 = take("/Size")
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(|| malformed("no /Size in the trailer"))?;
+        let root: u32 = take("/Root")
+            .and_then(|root| root.split_whitespace().next()?.parse().ok())
+            .ok_or_else(|| malformed("no /Root in the trailer"))?;
+        take("/Prev");
+
+        let mut update = Self {
+            pdf,
+            offsets,
+            root,
+            catalog: String::new(),
+            next_id: size,
+            objects: Vec::new(),
+            trailer,
+            previous_xref,
+        };
+        let catalog = update.object(root)?;
+        let catalog_end = catalog.rfind(">>").ok_or_else(|| malformed("catalog"))?;
+        update.catalog = catalog[..catalog_end].trim_end().to_owned();
+        Ok(update)
+    }
+
+    /// The body of object `id` in the original file, between `obj` and `endobj`.
+    ///
+    /// Only meant for dictionaries; stream data is not text.
+    pub fn object(&self, id: u32) -> Result<&str, Error> {
+        let start = self
+            .offsets
+            .get(id as usize)
+            .copied()
+            .flatten()
+            .ok_or_else(|| malformed(&format!("object {id} is missing")))?;
+        let bytes = &self.pdf[start..];
+        let end = bytes
+            .windows(6)
+            .position(|window| window == b"endobj")
+            .ok_or_else(|| malformed(&format!("object {id} never ends")))?;
+        let text = std::str::from_utf8(&bytes[..end])
+            .map_err(|_| malformed(&format!("object {id} is not a dictionary")))?;
+        let body_start = text
+            .find("obj")
+            .ok_or_else(|| malformed(&format!("object {id} has no header")))?;
+        Ok(text[body_start + 3..].trim())
+    }
+
+    /// Object numbers of the pages, in order. Expects the flat page tree krilla writes.
+    pub fn page_ids(&self) -> Result<Vec<u32>, Error> {
+        let pages: u32 = name_value(&self.catalog, "/Pages")
+            .and_then(|pages| pages.parse().ok())
+            .ok_or_else(|| malformed("no /Pages in the catalog"))?;
+        let pages = self.object(pages)?;
+        let kids = &pages[pages.find("/Kids").ok_or_else(|| malformed("no /Kids"))?..];
+        let (Some(start), Some(end)) = (kids.find('['), kids.find(']')) else {
+            return Err(malformed("/Kids is not an array"));
+        };
+        // References are `id generation R` triples.
+        kids[start + 1..end]
+            .split_whitespace()
+            .step_by(3)
+            .map(|id| id.parse().map_err(|_| malformed("/Kids")))
+            .collect()
+    }
+
+    /// Adds `body` as a new indirect object and returns its object number.
+    pub fn add_object(&mut self, body: String) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.objects.push((id, body));
+        id
+    }
+
+    /// Writes a new version of an existing object.
+    pub fn replace_object(&mut self, id: u32, body: String) {
+        self.objects.push((id, body));
+    }
+
+    /// Adds an entry such as `/PageMode /UseOutlines` to the document catalog.
+    pub fn set_catalog_entry(&mut self, entry: &str) {
+        self.catalog.push_str("\n  ");
+        self.catalog.push_str(entry);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let catalog = format!("{}\n>>", self.catalog);
+        let root = self.root;
+        self.objects.push((root, catalog));
+        self.objects.sort_by_key(|(id, _)| *id);
+
+        if !self.pdf.ends_with(b"\n") {
+            self.pdf.push(b'\n');
+        }
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (id, body) in &self.objects {
+            offsets.push((*id, self.pdf.len()));
+            self.pdf
+                .extend_from_slice(format!("{id} 0 obj\n{body}\nendobj\n\n").as_bytes());
+        }
+
+        let xref_offset = self.pdf.len();
+        let mut xref = String::from("xref\n");
+        // One subsection per run of consecutive object numbers.
+        let mut start = 0;
+        while start < offsets.len() {
+            let mut end = start + 1;
+            while end < offsets.len() && offsets[end].0 == offsets[end - 1].0 + 1 {
+                end += 1;
+            }
+            let _ = writeln!(xref, "{} {}", offsets[start].0, end - start);
+            for (_, offset) in &offsets[start..end] {
+                let _ = write!(xref, "{offset:010} 00000 n\r\n");
+            }
+            start = end;
+        }
+        let _ = write!(
+            xref,
+            "trailer\n<<\n  /Size {}\n  /Root {root} 0 R\n  /Prev {}\n",
+            self.next_id, self.previous_xref
+        );
+        for (key, value) in &self.trailer {
+            let _ = writeln!(xref, "  {key} {value}");
+        }
+        let _ = write!(xref, ">>\nstartxref\n{xref_offset}\n%%EOF\n");
+        self.pdf.extend_from_slice(xref.as_bytes());
+        self.pdf
+    }
+}
+
+/// Reads a classic cross-reference table into object offsets.
+fn parse_xref(xref: &str) -> Result<Vec<Option<usize>>, Error> {
+    let mut tokens = xref
+        .trim_start()
+        .strip_prefix("xref")
+        .ok_or_else(|| malformed("no cross-reference table"))?
+        .split_whitespace();
+    let mut offsets = Vec::new();
+    while let (Some(first), Some(count)) = (tokens.next(), tokens.next()) {
+        let first: usize = first.parse().map_err(|_| malformed("xref"))?;
+        let count: usize = count.parse().map_err(|_| malformed("xref"))?;
+        if offsets.len() < first + count {
+            offsets.resize(first + count, None);
+        }
+        for offset in &mut offsets[first..first + count] {
+            let (Some(position), Some(_), Some(kind)) =
+                (tokens.next(), tokens.next(), tokens.next())
+            else {
+                return Err(malformed("xref is truncated"));
+            };
+            if kind == "n" {
+                *offset = Some(position.parse().map_err(|_| malformed("xref"))?);
+            }
+        }
+    }
+    Ok(offsets)
+}
+
+/// The entries of the dictionary at the start of `text`, as key and value
+/// text. References such as `72 0 R` are kept together as one value.
+pub(crate) fn parse_dictionary(text: &str) -> Result<Vec<(String, String)>, Error> {
+    let (dict, _) = token(text).ok_or_else(|| malformed("expected a dictionary"))?;
+    let mut rest = dict
+        .strip_prefix("<<")
+        .and_then(|dict| dict.strip_suffix(">>"))
+        .ok_or_else(|| malformed(&format!("'{dict}' is not a dictionary")))?;
+    let mut entries = Vec::new();
+    while let Some((key, after_key)) = token(rest) {
+        if !key.starts_with('/') {
+            return Err(malformed(&format!("unexpected '{key}' in a dictionary")));
+        }
+        let (value, mut after_value) =
+            token(after_key).ok_or_else(|| malformed(&format!("{key} has no value")))?;
+        let mut value = value.to_owned();
+        // An indirect reference is three tokens: object number, generation, `R`.
+        if let Some((generation, after_generation)) = token(after_value) {
+            if let Some(("R", after_reference)) = token(after_generation) {
+                if value.parse::<u32>().is_ok() && generation.parse::<u32>().is_ok() {
+                    value = format!("{value} {generation} R");
+                    after_value = after_reference;
+                }
+            }
+        }
+        entries.push((key.to_owned(), value));
+        rest = after_value;
+    }
+    Ok(entries)
+}
+
+/// A dictionary with `entries`, as [`parse_dictionary`] returns them, laid
+/// out like krilla's for a dictionary nested `depth` levels deep.
+pub(crate) fn write_dictionary(entries: &[(String, String)], depth: usize) -> String {
+    let indent = "  ".repeat(depth);
+    let mut dict = "<<".to_owned();
+    for (key, value) in entries {
+        let _ = write!(dict, "\n{indent}  {key} {value}");
+    }
+    let _ = write!(dict, "\n{indent}>>");
+    dict
+}
+
+/// Splits the first PDF object token off `text`: a name, number, keyword,
+/// string, or a whole array or dictionary. `None` at the end of the text.
+fn token(text: &str) -> Option<(&str, &str)> {
+    let text = text.trim_start_matches(is_whitespace);
+    let bytes = text.as_bytes();
+    let end = match *bytes.first()? {
+        b'(' => {
+            let mut depth = 0;
+            let mut escaped = false;
+            let mut end = None;
+            for (i, byte) in bytes.iter().enumerate() {
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'(' => depth += 1,
+                    b')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            end = Some(i + 1);
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            end?
+        }
+        open @ (b'[' | b'<') if open == b'[' || text.starts_with("<<") => {
+            let close = if open == b'[' { "]" } else { ">>" };
+            let mut rest = &text[close.len()..];
+            loop {
+                rest = rest.trim_start_matches(is_whitespace);
+                if let Some(after) = rest.strip_prefix(close) {
+                    break text.len() - after.len();
+                }
+                rest = token(rest)?.1;
+            }
+        }
+        b'<' => text.find('>')? + 1,
+        b'/' => text[1..]
+            .find(is_delimiter)
+            .map_or(text.len(), |end| end + 1),
+        _ => text.find(is_delimiter).unwrap_or(text.len()).max(1),
+    };
+    Some((&text[..end], &text[end..]))
+}
+
+fn is_whitespace(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c' | '\0')
+}
+
+fn is_delimiter(c: char) -> bool {
+    is_whitespace(c) || "()<>[]{}/%".contains(c)
+}
+
+/// The token after `name`, e.g. `39` for `/Root` in `/Root 39 0 R`.
+fn name_value<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let start = text.find(name)? + name.len();
+    text[start..].split_whitespace().next()
+}
+
+fn malformed(reason: &str) -> Error {
+    Error::PdfUpdate(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use krilla::metadata::Metadata;
+    use krilla::{Document, PageSettings};
+
+    /// A one-page document as krilla writes it, with document properties.
+    fn krilla_pdf() -> Vec<u8> {
+        let mut doc = Document::new();
+        doc.set_metadata(Metadata::new().title("Test".to_owned()));
+        doc.start_page_with(PageSettings::new(200.0, 100.0))
+            .finish();
+        doc.finish().unwrap()
+    }
+
+    fn last_trailer(pdf: &[u8]) -> String {
+        let text = String::from_utf8_lossy(pdf);
+        text[text.rfind("trailer").unwrap()..].to_owned()
+    }
+
+    #[test]
+    fn update_repeats_the_previous_trailer() {
+        let pdf = krilla_pdf();
+        let original = last_trailer(&pdf);
+        assert!(original.contains("/Info"));
+        assert!(original.contains("/ID"));
+
+        let mut update = PdfUpdate::new(pdf).unwrap();
+        update.set_catalog_entry("/PageMode /UseOutlines");
+        let updated = update.finish();
+        let trailer = last_trailer(&updated);
+        let entries = parse_dictionary(&trailer["trailer".len()..]).unwrap();
+        let previous = parse_dictionary(&original["trailer".len()..]).unwrap();
+        for key in ["/Info", "/ID", "/Root"] {
+            let value = |entries: &[(String, String)]| {
+                entries
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value.clone())
+            };
+            assert_eq!(value(&entries), value(&previous), "{key}");
+        }
+        assert!(entries.iter().any(|(name, _)| name == "/Prev"));
+    }
+
+    #[test]
+    fn parses_dictionary_values() {
+        let entries =
+            parse_dictionary("<< /Size 5 /Info 3 0 R /ID [<AB> (a\\)b)] /Extra << /A [1 2] >> >>")
+                .unwrap();
+        let values: Vec<_> = entries.iter().map(|(_, value)| value.as_str()).collect();
+        assert_eq!(values, ["5", "3 0 R", "[<AB> (a\\)b)]", "<< /A [1 2] >>"]);
+    }
+}
@@ -0,0 +1,340 @@
+//! `.etracer` project files: the source image together with every layout setting.
+//!
+//! A project file starts with the magic bytes `ETRACER\0` and a little-endian
+//! `u32` format version. Sections follow, each prefixed with its length as a
+//! little-endian `u64`: the settings as RON text, the embedded image, and from
+//! version 2 one section per caption font file, in the order the settings'
+//! `fonts` list names them.
+
+use std::sync::Arc;
+
+use crate::caption::{Caption, CaptionFont};
+use crate::error::Error;
+use crate::export::TileJob;
+use crate::layout::{Anchor, Fit, Page, Roll, Sheet, SizeConstraint, Units};
+use crate::overlay::Overlays;
+use crate::resample::Resample;
+
+/// File extension used for project files.
+pub const EXTENSION: &str = "etracer";
+
+const MAGIC: &[u8; 8] = b"ETRACER\0";
+
+/// Version written into new project files.
+///
+/// Settings added later are filled in with their defaults when an older file
+/// is opened, so the version only changes when the file layout itself does.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Everything the user can tune about a poster's layout.
+#[derive(Debug, PartialEq, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct LayoutSettings {
+    pub desired_width: f32,
+    pub desired_height: f32,
+    pub units: Units,
+    pub page_size: Page,
+    /// Turn the pages to landscape.
+    pub landscape: bool,
+    /// Roll paper to print on instead of `page_size`.
+    pub roll: Option<Roll>,
+    pub maintain_aspect_ratio: bool,
+    /// Derive the size from this requirement instead of the width and height
+    /// entered, which it then overwrites.
+    pub size_constraint: Option<SizeConstraint>,
+    /// How the image fills the poster when their proportions differ.
+    pub fit: Fit,
+    /// Where the image is aligned when it does not fill the poster exactly.
+    pub anchor: Anchor,
+    /// Width of the strip shared by neighbouring pages, in inches.
+    pub overlap: f32,
+    /// Resolution to resample the image to on export, if any.
+    pub resample: Option<Resample>,
+    /// Embed a separate crop of the image on every page.
+    pub crop_tiles: bool,
+    /// Print a reminder to print at actual size on every page.
+    pub print_reminder: bool,
+    /// Printing aids drawn over the pages.
+    pub overlays: Overlays,
+    /// Large-format paper for a copy-shop print exported alongside the pages.
+    pub sheet: Option<Sheet>,
+    /// Text placed on the poster.
+    pub captions: Vec<Caption>,
+}
+
+impl Default for LayoutSettings {
+    fn default() -> Self {
+        Self {
+            desired_width: 8.26,
+            desired_height: 15.0,
+            units: Units::Inches,
+            page_size: Page::Letter,
+            landscape: false,
+            roll: None,
+            maintain_aspect_ratio: false,
+            size_constraint: None,
+            fit: Fit::Stretch,
+            anchor: Anchor::Center,
+            overlap: 0.0,
+            resample: None,
+            crop_tiles: false,
+            print_reminder: false,
+            overlays: Overlays::default(),
+            sheet: None,
+            captions: Vec::new(),
+        }
+    }
+}
+
+impl LayoutSettings {
+    /// The export job these settings describe.
+    pub fn job(&self) -> TileJob {
+        TileJob::new(self.desired_width, self.desired_height)
+            .with_page(self.page_size)
+            .with_landscape(self.landscape)
+            .with_roll(self.roll)
+            .with_overlap(self.overlap)
+            .with_fit(self.fit, self.anchor)
+            .with_resample(self.resample)
+            .with_crop_tiles(self.crop_tiles)
+            .with_print_reminder(self.print_reminder)
+            .with_overlays(self.overlays)
+            .with_sheet(self.sheet)
+            .with_captions(self.captions.clone())
+    }
+}
+
+/// A saved poster: the image exactly as it is handed to the PDF writer, and its layout.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Project {
+    pub settings: LayoutSettings,
+    /// Resolution embedded in the original file, which re-encoded images no longer carry.
+    pub image_dpi: Option<(f32, f32)>,
+    /// The poster's name, which becomes the PDF title.
+    pub name: Option<String>,
+    pub image: Vec<u8>,
+}
+
+/// The RON encoded part of a project file.
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+struct Header {
+    settings: LayoutSettings,
+    image_dpi: Option<(f32, f32)>,
+    name: Option<String>,
+    /// Names of the caption font files embedded after the image.
+    fonts: Vec<String>,
+    /// For each caption, the embedded font file it uses. Files are told apart
+    /// by their data, since fonts from different families often share a name.
+    caption_fonts: Vec<Option<usize>>,
+}
+
+impl Project {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Each font file once, however many captions use it.
+        let mut fonts: Vec<(&String, &[u8])> = Vec::new();
+        let mut caption_fonts = Vec::new();
+        for caption in &self.settings.captions {
+            let section = match &caption.font {
+                CaptionFont::File { name, data } if !caption.font.is_missing() => {
+                    let known = fonts
+                        .iter()
+                        .position(|(_, known)| *known == data.as_slice());
+                    Some(known.unwrap_or_else(|| {
+                        fonts.push((name, data));
+                        fonts.len() - 1
+                    }))
+                }
+                _ => None,
+            };
+            caption_fonts.push(section);
+        }
+        let header = Header {
+            settings: self.settings.clone(),
+            image_dpi: self.image_dpi,
+            name: self.name.clone(),
+            fonts: fonts.iter().map(|(name, _)| (*name).clone()).collect(),
+            caption_fonts,
+        };
+        let header = ron::ser::to_string_pretty(&header, ron::ser::PrettyConfig::default())
+            .expect("project settings are always serialisable");
+
+        let mut data = Vec::with_capacity(MAGIC.len() + 20 + header.len() + self.image.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        let fonts = fonts.iter().map(|(_, data)| *data);
+        for section in [header.as_bytes(), &self.image].into_iter().chain(fonts) {
+            data.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            data.extend_from_slice(section);
+        }
+        data
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let mut rest = data.strip_prefix(MAGIC).ok_or(Error::InvalidProject(
+            "not an etracer project file".to_owned(),
+        ))?;
+        let version = u32::from_le_bytes(take(&mut rest, 4)?.try_into().unwrap());
+        if version > FORMAT_VERSION {
+            return Err(Error::UnsupportedProjectVersion(version));
+        }
+        let header = take_section(&mut rest)?;
+        let image = take_section(&mut rest)?.to_vec();
+
+        let header =
+            std::str::from_utf8(header).map_err(|err| Error::InvalidProject(err.to_string()))?;
+        let mut header: Header =
+            ron::from_str(header).map_err(|err| Error::InvalidProject(err.to_string()))?;
+        let fonts = (0..header.fonts.len())
+            .map(|_| Ok(Arc::new(take_section(&mut rest)?.to_vec())))
+            .collect::<Result<Vec<_>, Error>>()?;
+        for (caption, section) in header
+            .settings
+            .captions
+            .iter_mut()
+            .zip(&header.caption_fonts)
+        {
+            if let (CaptionFont::File { data, .. }, Some(section)) = (&mut caption.font, section) {
+                *data = fonts
+                    .get(*section)
+                    .ok_or_else(|| Error::InvalidProject("missing font file".to_owned()))?
+                    .clone();
+            }
+        }
+        Ok(Self {
+            settings: header.settings,
+            image_dpi: header.image_dpi,
+            name: header.name,
+            image,
+        })
+    }
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if data.len() < len {
+        return Err(Error::InvalidProject("file is truncated".to_owned()));
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn take_section<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let len = u64::from_le_bytes(take(data, 8)?.try_into().unwrap());
+    let len = usize::try_from(len)
+        .map_err(|_| Error::InvalidProject("section is too large".to_owned()))?;
+    take(data, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caption::TextSize;
+
+    fn font(name: &str, data: &[u8]) -> CaptionFont {
+        CaptionFont::File {
+            name: name.to_owned(),
+            data: Arc::new(data.to_vec()),
+        }
+    }
+
+    fn project() -> Project {
+        let caption = |text: &str, font| Caption {
+            text: text.to_owned(),
+            size: TextSize::Length(2.0),
+            font,
+            ..Caption::default()
+        };
+        Project {
+            settings: LayoutSettings {
+                desired_width: 36.0,
+                page_size: Page::A4,
+                landscape: true,
+                overlap: 0.25,
+                captions: vec![
+                    caption("Title", font("Title.otf", b"title font")),
+                    caption("Note", CaptionFont::Mono),
+                    caption("Subtitle", font("Title.otf", b"title font")),
+                    caption("Credit", font("Credit.ttf", b"credit font")),
+                    caption("Other", font("Title.otf", b"another font")),
+                ],
+                ..LayoutSettings::default()
+            },
+            image_dpi: Some((300.0, 300.0)),
+            name: Some("Map".to_owned()),
+            image: vec![0x89, b'P', b'N', b'G', 0, 1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let project = project();
+        let data = project.to_bytes();
+        assert_eq!(Project::from_bytes(&data).unwrap(), project);
+        // The font used twice is embedded once.
+        let embedded = data.windows(10).filter(|w| w == b"title font").count();
+        assert_eq!(embedded, 1);
+        // Files sharing a name stay apart.
+        let sections = data.windows(12).filter(|w| w == b"another font").count();
+        assert_eq!(sections, 1);
+    }
+
+    #[test]
+    fn missing_fonts_stay_missing() {
+        let mut project = project();
+        project.settings.captions[3].font = font("Credit.ttf", b"");
+        let loaded = Project::from_bytes(&project.to_bytes()).unwrap();
+        assert!(loaded.settings.captions[3].font.is_missing());
+        assert!(!loaded.settings.captions[0].font.is_missing());
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let data = project().to_bytes();
+        for len in 0..data.len() {
+            assert!(
+                Project::from_bytes(&data[..len]).is_err(),
+                "truncated to {len} bytes"
+            );
+        }
+    }
+
+    #[test]
+    fn other_files_are_rejected() {
+        let mut data = project().to_bytes();
+        data[0] = b'X';
+        assert!(matches!(
+            Project::from_bytes(&data),
+            Err(Error::InvalidProject(_))
+        ));
+        assert!(Project::from_bytes(b"%PDF-1.7").is_err());
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let mut data = project().to_bytes();
+        data[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            Project::from_bytes(&data),
+            Err(Error::UnsupportedProjectVersion(version)) if version == FORMAT_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn version_one_files_still_open() {
+        let header = "(settings: (desired_width: 20.0, page_size: Legal), name: Some(\"Old\"))";
+        let image = [1u8, 2, 3];
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for section in [header.as_bytes(), &image] {
+            data.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            data.extend_from_slice(section);
+        }
+        let project = Project::from_bytes(&data).unwrap();
+        assert_eq!(project.settings.desired_width, 20.0);
+        assert_eq!(project.settings.page_size, Page::Legal);
+        assert_eq!(project.settings.overlap, LayoutSettings::default().overlap);
+        assert_eq!(project.name.as_deref(), Some("Old"));
+        assert_eq!(project.image, image);
+    }
+}
@@ -0,0 +1,255 @@
+//! Decoding the files a poster is made from: PNG, JPEG and SVG.
+//!
+//! Raster images are turned the right way up from their EXIF orientation and
+//! keep the resolution stored in the file, which sets the poster's native
+//! size. SVG files are rendered only for the preview and exported as vectors.
+
+use std::io::Cursor;
+
+use image::error::{DecodingError, ImageFormatHint};
+use image::{DynamicImage, ImageError, ImageFormat, ImageResult, RgbaImage};
+use resvg::{tiny_skia, usvg};
+
+/// Longest side, in pixels, of the preview rendered for SVG files.
+const SVG_PREVIEW_SIDE: f32 = 4096.0;
+
+/// SVG user units per inch.
+const SVG_UNITS_PER_INCH: f32 = 96.0;
+
+/// A decoded source image, together with the bytes that get embedded into the PDF.
+///
+/// Both `image` and `bytes` have the EXIF orientation applied, so the preview
+/// and the exported PDF always agree on which way is up.
+pub struct SourceImage {
+    pub image: DynamicImage,
+    pub bytes: Vec<u8>,
+    /// Resolution stored in the file as (horizontal, vertical) dots per inch.
+    pub dpi: Option<(f32, f32)>,
+    /// The source is an SVG file, exported as vectors. `image` is only a preview.
+    pub vector: bool,
+}
+
+impl SourceImage {
+    pub fn decode(data: Vec<u8>) -> ImageResult<Self> {
+        if is_svg(&data) {
+            return Self::decode_svg(data);
+        }
+        let format = image::guess_format(&data)?;
+        let image = image::load_from_memory_with_format(&data, format)?;
+        let exif = exif::Reader::new()
+            .read_from_container(&mut Cursor::new(&data))
+            .ok();
+        let orientation = exif
+            .as_ref()
+            .and_then(|exif| exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY))
+            .and_then(|field| field.value.get_uint(0))
+            .unwrap_or(1);
+        let mut dpi = match format {
+            ImageFormat::Png => png_dpi(&data),
+            ImageFormat::Jpeg => jfif_dpi(&data),
+            _ => None,
+        }
+        .or_else(|| exif.as_ref().and_then(exif_dpi));
+
+        if orientation == 1 {
+            return Ok(Self {
+                image,
+                bytes: data,
+                dpi,
+                vector: false,
+            });
+        }
+
+        let image = apply_orientation(image, orientation);
+        if (5..=8).contains(&orientation) {
+            dpi = dpi.map(|(x, y)| (y, x));
+        }
+        // Re-encode so the PDF embeds the pixels the right way up.
+        let bytes = encode(&image, format)?;
+        Ok(Self {
+            image,
+            bytes,
+            dpi,
+            vector: false,
+        })
+    }
+
+    /// Renders a preview of an SVG file on white paper. Its resolution is the
+    /// preview's, so that the SVG's own width and height give the native size.
+    fn decode_svg(data: Vec<u8>) -> ImageResult<Self> {
+        let tree = parse_svg(&data)?;
+        let size = tree.size();
+        let scale = SVG_PREVIEW_SIDE / size.width().max(size.height());
+        let width = (size.width() * scale).round().max(1.0) as u32;
+        let height = (size.height() * scale).round().max(1.0) as u32;
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| svg_error(format!("cannot render {width} x {height} pixels")))?;
+        pixmap.fill(tiny_skia::Color::WHITE);
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+        // The paper is opaque, so premultiplied and straight alpha agree.
+        let image = RgbaImage::from_raw(width, height, pixmap.take())
+            .expect("pixmap holds width x height pixels");
+        let dpi = (
+            width as f32 / (size.width() / SVG_UNITS_PER_INCH),
+            height as f32 / (size.height() / SVG_UNITS_PER_INCH),
+        );
+        Ok(Self {
+            image: DynamicImage::ImageRgba8(image),
+            bytes: data,
+            dpi: Some(dpi),
+            vector: true,
+        })
+    }
+}
+
+/// Whether `data` looks like an SVG file rather than a raster image.
+pub(crate) fn is_svg(data: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&data[..data.len().min(1024)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    head.starts_with('<') && head.contains("<svg")
+}
+
+/// Parses an SVG file. Text is set in the fonts available to usvg, which are
+/// none on the web, so SVGs with text should have it converted to paths.
+pub(crate) fn parse_svg(data: &[u8]) -> ImageResult<usvg::Tree> {
+    usvg::Tree::from_data(data, &usvg::Options::default()).map_err(|err| svg_error(err.to_string()))
+}
+
+fn svg_error(message: String) -> ImageError {
+    ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Name("SVG".to_owned()),
+        message,
+    ))
+}
+
+/// Encodes `image` for embedding into the PDF. JPEG stays JPEG to keep the file
+/// size in check; anything else becomes a lossless PNG.
+pub(crate) fn encode(image: &DynamicImage, format: ImageFormat) -> ImageResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    if format == ImageFormat::Jpeg {
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut bytes, 95)
+            .encode_image(&image.to_rgb8())?;
+    } else {
+        image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    }
+    Ok(bytes)
+}
+
+/// Applies an EXIF orientation tag (1-8) to the decoded pixels.
+fn apply_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Reads the `pHYs` chunk of a PNG file.
+fn png_dpi(data: &[u8]) -> Option<(f32, f32)> {
+    // Skip the 8 byte signature, then walk the chunks: length, type, data, crc.
+    let mut pos = 8;
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        // A crafted length could overflow `usize` on 32-bit targets.
+        let end = (pos + 8).checked_add(length)?;
+        let body = data.get(pos + 8..end)?;
+        match kind {
+            b"pHYs" if length == 9 => {
+                let x = u32::from_be_bytes(body[0..4].try_into().ok()?);
+                let y = u32::from_be_bytes(body[4..8].try_into().ok()?);
+                // Unit 1 is pixels per metre; unit 0 only describes the aspect ratio.
+                if body[8] != 1 || x == 0 || y == 0 {
+                    return None;
+                }
+                return Some((x as f32 * 0.0254, y as f32 * 0.0254));
+            }
+            b"IDAT" | b"IEND" => return None,
+            _ => pos = end.checked_add(4)?,
+        }
+    }
+    None
+}
+
+/// Reads the density fields of a JFIF `APP0` segment.
+fn jfif_dpi(data: &[u8]) -> Option<(f32, f32)> {
+    let segment = data.get(2..20)?;
+    if segment[0..2] != [0xFF, 0xE0] || &segment[4..9] != b"JFIF\0" {
+        return None;
+    }
+    let x = u16::from_be_bytes([segment[12], segment[13]]) as f32;
+    let y = u16::from_be_bytes([segment[14], segment[15]]) as f32;
+    if x == 0.0 || y == 0.0 {
+        return None;
+    }
+    match segment[11] {
+        1 => Some((x, y)),
+        2 => Some((x * 2.54, y * 2.54)),
+        _ => None,
+    }
+}
+
+/// Reads `XResolution`/`YResolution` from EXIF, honouring `ResolutionUnit`.
+fn exif_dpi(exif: &exif::Exif) -> Option<(f32, f32)> {
+    let rational = |tag| match &exif.get_field(tag, exif::In::PRIMARY)?.value {
+        exif::Value::Rational(values) => values.first().map(|r| r.to_f64() as f32),
+        _ => None,
+    };
+    let x = rational(exif::Tag::XResolution)?;
+    let y = rational(exif::Tag::YResolution).unwrap_or(x);
+    if !(x > 0.0 && y > 0.0) {
+        return None;
+    }
+    let unit = exif
+        .get_field(exif::Tag::ResolutionUnit, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .unwrap_or(2);
+    match unit {
+        2 => Some((x, y)),
+        3 => Some((x * 2.54, y * 2.54)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(chunks: &[(&[u8; 4], u32, &[u8])]) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, length, body) in chunks {
+            data.extend_from_slice(&length.to_be_bytes());
+            data.extend_from_slice(*kind);
+            data.extend_from_slice(body);
+            data.extend_from_slice(&[0; 4]);
+        }
+        data
+    }
+
+    #[test]
+    fn png_resolution_is_read() {
+        // 11811 pixels per metre is 300 dpi.
+        let mut phys = 11811u32.to_be_bytes().repeat(2);
+        phys.push(1);
+        let data = png(&[(b"IHDR", 13, &[0; 13]), (b"pHYs", 9, &phys)]);
+        let (x, y) = png_dpi(&data).unwrap();
+        assert!((x - 300.0).abs() < 0.01 && (y - 300.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn bad_png_chunk_lengths_are_ignored() {
+        for length in [u32::MAX, u32::MAX - 7, 1000] {
+            let data = png(&[(b"tEXt", length, b"short")]);
+            assert_eq!(png_dpi(&data), None);
+        }
+    }
+}
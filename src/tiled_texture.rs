@@ -0,0 +1,103 @@
+//! Preview textures for images larger than the GPU allows in one piece.
+
+use std::sync::Arc;
+
+use egui::{Color32, ColorImage, Painter, Pos2, Rect, TextureHandle, TextureOptions};
+use image::DynamicImage;
+
+/// Images with more pixels are shrunk for the preview. Export is unaffected
+/// and always uses the full resolution.
+const MAX_PREVIEW_PIXELS: u64 = 64_000_000;
+
+/// Pixels of one texture, ready to upload, and the part of the image they
+/// show in normalised image coordinates.
+pub type TextureChunk = (ColorImage, Rect);
+
+/// An image uploaded as a grid of textures, each within the GPU's size limit.
+///
+/// The pixels are kept so the textures can be uploaded again with other
+/// sampling options without preparing them anew.
+pub struct TiledTexture {
+    chunks: Vec<(TextureHandle, Arc<ColorImage>, Rect)>,
+}
+
+impl TiledTexture {
+    /// Converts `image` into chunks no larger than `max_side` pixels.
+    ///
+    /// This is the slow part of loading a preview and needs no GPU access.
+    pub fn prepare(image: &DynamicImage, max_side: usize) -> Vec<TextureChunk> {
+        let pixels = image.width() as u64 * image.height() as u64;
+        let preview = if pixels > MAX_PREVIEW_PIXELS {
+            let factor = (MAX_PREVIEW_PIXELS as f64 / pixels as f64).sqrt();
+            image.thumbnail(
+                (image.width() as f64 * factor) as u32,
+                (image.height() as f64 * factor) as u32,
+            )
+        } else {
+            image.clone()
+        };
+        let rgba = preview.to_rgba8();
+        let (width, height) = rgba.dimensions();
+        let max_side = max_side.max(1) as u32;
+
+        let mut chunks = Vec::new();
+        for y in (0..height).step_by(max_side as usize) {
+            for x in (0..width).step_by(max_side as usize) {
+                let w = max_side.min(width - x);
+                let h = max_side.min(height - y);
+                let view = image::imageops::crop_imm(&rgba, x, y, w, h).to_image();
+                let pixels = ColorImage::from_rgba_unmultiplied(
+                    [w as usize, h as usize],
+                    view.as_flat_samples().as_slice(),
+                );
+                let uv = Rect::from_min_max(
+                    Pos2::new(x as f32 / width as f32, y as f32 / height as f32),
+                    Pos2::new(
+                        (x + w) as f32 / width as f32,
+                        (y + h) as f32 / height as f32,
+                    ),
+                );
+                chunks.push((pixels, uv));
+            }
+        }
+        chunks
+    }
+
+    pub fn upload(ctx: &egui::Context, chunks: Vec<TextureChunk>, options: TextureOptions) -> Self {
+        let chunks = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (pixels, uv))| {
+                let pixels = Arc::new(pixels);
+                let texture = ctx.load_texture(format!("image {i}"), pixels.clone(), options);
+                (texture, pixels, uv)
+            })
+            .collect();
+        Self { chunks }
+    }
+
+    /// Uploads the same pixels again, sampled with `options`.
+    pub fn set_options(&mut self, options: TextureOptions) {
+        for (texture, pixels, _) in &mut self.chunks {
+            texture.set(pixels.clone(), options);
+        }
+    }
+
+    /// Paints the part of the image inside `uv` into `rect` on screen.
+    pub fn paint(&self, painter: &Painter, rect: Rect, uv: Rect) {
+        for (texture, _, chunk_uv) in &self.chunks {
+            let part = uv.intersect(*chunk_uv);
+            if part.width() <= 0.0 || part.height() <= 0.0 {
+                continue;
+            }
+            let to_screen = |p: Pos2| rect.min + ((p - uv.min) / uv.size()) * rect.size();
+            let to_chunk = |p: Pos2| Pos2::ZERO + (p - chunk_uv.min) / chunk_uv.size();
+            painter.image(
+                texture.id(),
+                Rect::from_min_max(to_screen(part.min), to_screen(part.max)),
+                Rect::from_min_max(to_chunk(part.min), to_chunk(part.max)),
+                Color32::WHITE,
+            );
+        }
+    }
+}